@@ -0,0 +1,318 @@
+use super::{
+    commit_fresh, readback_size, run_schedule, target_images, Backend, ReadbackTarget,
+    RenderMonitor, RenderResult, StepEvaluator,
+};
+use crate::cache::NodeCache;
+use crate::color_space::{linear_to_srgb, ColorSpace};
+use crate::error::EngineError;
+use crate::graph::{Graph, Size};
+use crate::nodes::{CpuContext, CpuOutputs, NodeRegistry};
+use crate::samplers::{AddressMode, Filter, SamplerSettings};
+use crate::scheduler::{OutputKey, Schedule, Step};
+use crate::state::{OutputEncoding, RenderedImage};
+use crate::stats::{unique_bytes, RenderStats, TimingSource};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// An RGBA image in a working color space, with channels in `0.0..=1.0`,
+/// row by row.
+///
+/// Values are rounded when the image is built the way the GPU backend's
+/// textures in that space store them, so both backends feed the same values
+/// into downstream nodes.
+#[derive(Debug, Clone)]
+pub struct CpuImage {
+    pub size: Size,
+    pub space: ColorSpace,
+    pixels: Vec<[f32; 4]>,
+}
+
+/// Rounds a channel value to what a texture of `space`'s format holds.
+fn store(space: ColorSpace, value: f32) -> f32 {
+    match space {
+        ColorSpace::Linear => half::f16::from_f32(value).to_f32(),
+        ColorSpace::Gamma => (value.clamp(0.0, 1.0) * 255.0).round() / 255.0,
+    }
+}
+
+impl CpuImage {
+    /// Builds an image from the color of each pixel.
+    pub fn from_fn(
+        size: Size,
+        space: ColorSpace,
+        mut pixel: impl FnMut(u32, u32) -> [f32; 4],
+    ) -> Self {
+        let mut pixels = Vec::with_capacity(size.width as usize * size.height as usize);
+        for y in 0..size.height {
+            for x in 0..size.width {
+                pixels.push(pixel(x, y).map(|c| store(space, c)));
+            }
+        }
+        Self {
+            size,
+            space,
+            pixels,
+        }
+    }
+
+    pub fn filled(size: Size, space: ColorSpace, color: [f32; 4]) -> Self {
+        Self::from_fn(size, space, |_, _| color)
+    }
+
+    /// The memory taken by the pixels.
+    pub fn bytes(&self) -> u64 {
+        std::mem::size_of_val(self.pixels.as_slice()) as u64
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        self.pixels[(y * self.size.width + x) as usize]
+    }
+
+    /// The texel at `x`, `y`, which may lie outside the image, resolved
+    /// with `address`.
+    fn texel(&self, x: i64, y: i64, address: AddressMode) -> [f32; 4] {
+        match (
+            address.resolve(x, self.size.width),
+            address.resolve(y, self.size.height),
+        ) {
+            (Some(x), Some(y)) => self.pixel(x, y),
+            _ => [0.0; 4],
+        }
+    }
+
+    /// Samples at normalized coordinates like a GPU sampler with `sampler`'s
+    /// settings.
+    pub fn sample(&self, [u, v]: [f32; 2], sampler: SamplerSettings) -> [f32; 4] {
+        let texel = |x, y| self.texel(x, y, sampler.address);
+        match sampler.filter {
+            Filter::Nearest => {
+                let nearest = |coord: f32, extent: u32| (coord * extent as f32).floor() as i64;
+                texel(nearest(u, self.size.width), nearest(v, self.size.height))
+            }
+            Filter::Linear => {
+                let (x, fx) = linear_tap(u, self.size.width);
+                let (y, fy) = linear_tap(v, self.size.height);
+                let top = mix(texel(x, y), texel(x + 1, y), fx);
+                let bottom = mix(texel(x, y + 1), texel(x + 1, y + 1), fx);
+                mix(top, bottom, fy)
+            }
+        }
+    }
+
+    /// Samples at normalized coordinates like a nearest, clamp-to-edge
+    /// sampler, the default.
+    pub fn sample_nearest(&self, uv: [f32; 2]) -> [f32; 4] {
+        self.sample(uv, SamplerSettings::default())
+    }
+
+    /// Converts to `encoding` at `size`, scaling with nearest sampling like
+    /// the GPU output stage does.
+    pub fn to_rendered(&self, size: Size, encoding: OutputEncoding) -> RenderedImage {
+        let bytes = encoding.bytes_per_pixel() as usize;
+        let mut pixels = Vec::with_capacity(size.width as usize * size.height as usize * bytes);
+        for y in 0..size.height {
+            for x in 0..size.width {
+                let color = if size == self.size {
+                    self.pixel(x, y)
+                } else {
+                    self.sample_nearest(pixel_uv(size, x, y))
+                };
+                encode_pixel(color, self.space, encoding, &mut pixels);
+            }
+        }
+        RenderedImage {
+            width: size.width,
+            height: size.height,
+            encoding,
+            pixels,
+        }
+    }
+}
+
+fn unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Rec. 709 luma, matching `fs_gray` in the output shader.
+pub fn luma([r, g, b, _]: [f32; 4]) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn encode_pixel(color: [f32; 4], space: ColorSpace, encoding: OutputEncoding, out: &mut Vec<u8>) {
+    match encoding {
+        OutputEncoding::Srgb8 if space == ColorSpace::Linear => {
+            let [r, g, b, a] = color;
+            let encode = linear_to_srgb;
+            out.extend([encode(r), encode(g), encode(b), a].map(unorm8));
+        }
+        OutputEncoding::Rgba8 | OutputEncoding::Srgb8 => out.extend(color.map(unorm8)),
+        OutputEncoding::Gray8 => out.push(unorm8(luma(color))),
+        OutputEncoding::Rgba16f => {
+            for c in color {
+                out.extend(half::f16::from_f32(c).to_le_bytes());
+            }
+        }
+    }
+}
+
+/// The first of the two texels a linear sampler blends along one axis, and
+/// the weight of the second.
+fn linear_tap(coord: f32, extent: u32) -> (i64, f32) {
+    let texel = coord * extent as f32 - 0.5;
+    let base = texel.floor();
+    (base as i64, texel - base)
+}
+
+/// Component-wise `a + (b - a) * t`, as WGSL's `mix`.
+pub fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] * (1.0 - t) + b[i] * t)
+}
+
+/// The normalized coordinates of the center of pixel (`x`, `y`), which is
+/// what the full-screen vertex stage interpolates for fragment shaders.
+pub fn pixel_uv(size: Size, x: u32, y: u32) -> [f32; 2] {
+    [
+        (x as f32 + 0.5) / size.width as f32,
+        (y as f32 + 0.5) / size.height as f32,
+    ]
+}
+
+/// Runs the CPU side of node processors for one render.
+pub struct CpuEvaluator<'a> {
+    pub registry: &'a NodeRegistry,
+    /// Output table for this render, keyed by node id and output handle.
+    pub outputs: HashMap<OutputKey, Arc<CpuImage>>,
+    pub canvas: Size,
+    pub color_space: ColorSpace,
+}
+
+impl CpuEvaluator<'_> {
+    /// Looks up the image wired into `handle`, or black at `size` if nothing
+    /// is connected.
+    pub fn input(&self, step: &Step, handle: &str, size: Size) -> Result<Arc<CpuImage>, String> {
+        match step.input(handle) {
+            Some(key) => self.outputs.get(key).cloned().ok_or_else(|| {
+                format!(
+                    "Output '{}' of node {} has not been rendered",
+                    key.handle, key.node_id
+                )
+            }),
+            None => Ok(Arc::new(CpuImage::filled(
+                size,
+                self.color_space,
+                [0.0, 0.0, 0.0, 1.0],
+            ))),
+        }
+    }
+}
+
+impl StepEvaluator for CpuEvaluator<'_> {
+    type Image = Arc<CpuImage>;
+
+    fn canvas(&self) -> Size {
+        self.canvas
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn registry(&self) -> &NodeRegistry {
+        self.registry
+    }
+
+    fn output(&self, key: &OutputKey) -> Option<&Self::Image> {
+        self.outputs.get(key)
+    }
+
+    fn insert_output(&mut self, key: OutputKey, image: Self::Image) {
+        self.outputs.insert(key, image);
+    }
+
+    fn remove_output(&mut self, key: &OutputKey) -> Option<Self::Image> {
+        self.outputs.remove(key)
+    }
+
+    fn image_size(image: &Self::Image) -> Size {
+        image.size
+    }
+
+    fn evaluate(&mut self, step: &Step, size: Size) -> Result<CpuOutputs, EngineError> {
+        let node_type = step.node.kind.type_name();
+        let processor = self.registry.get(node_type).ok_or_else(|| {
+            EngineError::node(
+                &step.node.id,
+                format!("No processor registered for {}", node_type),
+            )
+        })?;
+
+        let mut ctx = CpuContext {
+            evaluator: self,
+            step,
+            size,
+        };
+        processor.process_cpu(&mut ctx)
+    }
+}
+
+/// Renders every node in plain Rust. Used where no GPU adapter is available
+/// and as the reference the shaders are checked against.
+#[derive(Default)]
+pub struct CpuBackend {
+    cache: NodeCache<Arc<CpuImage>>,
+}
+
+impl CpuBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Backend for CpuBackend {
+    fn name(&self) -> String {
+        "CPU reference renderer".to_string()
+    }
+
+    fn render(
+        &mut self,
+        graph: &Graph,
+        registry: &NodeRegistry,
+        targets: &[ReadbackTarget],
+        monitor: &dyn RenderMonitor,
+    ) -> Result<RenderResult, EngineError> {
+        let started = Instant::now();
+        let target_ids: Vec<&str> = targets.iter().map(|t| t.key.node_id.as_str()).collect();
+        let schedule = Schedule::build(graph, &target_ids)?;
+
+        let mut evaluator = CpuEvaluator {
+            registry,
+            outputs: HashMap::new(),
+            canvas: graph.canvas,
+            color_space: graph.color_space,
+        };
+        let scheduled = run_schedule(&mut evaluator, &schedule, &self.cache, targets, monitor)?;
+        let images = target_images(&evaluator, targets)?
+            .iter()
+            .zip(targets)
+            .map(|(image, target)| {
+                image.to_rendered(readback_size(target, image.size), target.encoding)
+            })
+            .collect();
+        let rendered_nodes = commit_fresh(&mut self.cache, scheduled.fresh, graph, drop);
+
+        let stats = RenderStats::new(
+            self.name(),
+            TimingSource::Cpu,
+            started.elapsed(),
+            scheduled.nodes,
+            unique_bytes(self.cache.images(), CpuImage::bytes),
+            0,
+        );
+        Ok(RenderResult {
+            images,
+            rendered_nodes,
+            stats,
+        })
+    }
+}
@@ -0,0 +1,214 @@
+use super::{
+    commit_fresh, readback_size, run_schedule, target_images, Backend, ReadbackTarget,
+    RenderMonitor, RenderResult, StepEvaluator,
+};
+use crate::cache::NodeCache;
+use crate::error::EngineError;
+use crate::evaluator::Evaluator;
+use crate::graph::{Graph, MAX_SIZE};
+use crate::nodes::NodeRegistry;
+use crate::pipelines::PipelineCache;
+use crate::readback::Readback;
+use crate::samplers::SamplerCache;
+use crate::scheduler::Schedule;
+use crate::state::RenderContext;
+use crate::stats::{unique_bytes, RenderStats, TimingSource};
+use crate::textures::{texture_bytes, TexturePool};
+use crate::timestamps::{self, NodeTimer};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Renders through wgpu, keeping node outputs as textures between renders.
+pub struct GpuBackend {
+    pub context: RenderContext,
+    cache: NodeCache<Arc<wgpu::Texture>>,
+    /// Why the device was lost, set by its device-lost callback.
+    lost: Arc<Mutex<Option<String>>>,
+}
+
+impl GpuBackend {
+    /// Sets up a device on the best adapter available. Fails if there is none.
+    pub async fn request() -> Result<Self, EngineError> {
+        let instance = wgpu::Instance::default();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok_or_else(|| EngineError::gpu("Failed to find an appropriate adapter"))?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Pixel Forge Device"),
+                    // Optional features, used where the adapter has them.
+                    required_features: adapter.features() & timestamps::FEATURES,
+                    // As large as the graph may ask for, where the adapter
+                    // allows it; smaller adapters reject bigger nodes as they
+                    // render.
+                    required_limits: wgpu::Limits {
+                        max_texture_dimension_2d: adapter
+                            .limits()
+                            .max_texture_dimension_2d
+                            .min(MAX_SIZE),
+                        ..wgpu::Limits::downlevel_defaults()
+                    },
+                    memory_hints: wgpu::MemoryHints::Performance,
+                },
+                None,
+            )
+            .await
+            .map_err(|e| EngineError::gpu(format!("Failed to create device: {}", e)))?;
+
+        let lost = Arc::new(Mutex::new(None));
+        let reason = lost.clone();
+        device.set_device_lost_callback(move |kind, message| {
+            // Dropping the device when it is replaced calls back too.
+            if matches!(
+                kind,
+                wgpu::DeviceLostReason::Dropped | wgpu::DeviceLostReason::ReplacedCallback
+            ) {
+                return;
+            }
+            if let Ok(mut reason) = reason.lock() {
+                *reason = Some(if message.is_empty() {
+                    format!("{:?}", kind)
+                } else {
+                    format!("{:?}: {}", kind, message)
+                });
+            }
+        });
+        // Errors outside the scopes around nodes and pipelines would panic
+        // by default, taking the render thread down with them.
+        device.on_uncaptured_error(Box::new(|error| {
+            eprintln!("Uncaptured GPU error: {}", error);
+        }));
+
+        Ok(Self {
+            context: RenderContext {
+                instance,
+                adapter,
+                device,
+                queue,
+                pipelines: PipelineCache::new(),
+                samplers: SamplerCache::new(),
+                textures: TexturePool::new(),
+            },
+            cache: NodeCache::new(),
+            lost,
+        })
+    }
+}
+
+impl Backend for GpuBackend {
+    fn name(&self) -> String {
+        format!("WebGPU on {:?}", self.context.adapter.get_info().name)
+    }
+
+    fn lost_device(&self) -> Option<String> {
+        self.lost.lock().ok()?.clone()
+    }
+
+    /// Requests a new adapter and device, as the old adapter may be the
+    /// reason the device was lost.
+    fn reset(&mut self) -> Result<(), EngineError> {
+        *self = pollster::block_on(Self::request())?;
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        graph: &Graph,
+        registry: &NodeRegistry,
+        targets: &[ReadbackTarget],
+        monitor: &dyn RenderMonitor,
+    ) -> Result<RenderResult, EngineError> {
+        let started = Instant::now();
+        let context = &self.context;
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        let target_ids: Vec<&str> = targets.iter().map(|t| t.key.node_id.as_str()).collect();
+        let schedule = Schedule::build(graph, &target_ids)?;
+        let frame = context.textures.frame();
+
+        let (scheduled, textures, timer) = {
+            let mut evaluator = Evaluator {
+                context,
+                registry,
+                encoder: &mut encoder,
+                outputs: HashMap::new(),
+                canvas: graph.canvas,
+                color_space: graph.color_space,
+                transient: Vec::new(),
+                timer: NodeTimer::new(&context.device, schedule.steps.len()),
+            };
+            let scheduled = run_schedule(&mut evaluator, &schedule, &self.cache, targets, monitor)?;
+            let textures = target_images(&evaluator, targets)?;
+            (scheduled, textures, evaluator.timer)
+        };
+
+        let readbacks = targets
+            .iter()
+            .zip(&textures)
+            .map(|(target, texture)| {
+                let size = readback_size(target, Evaluator::image_size(texture));
+                Readback::record(
+                    context,
+                    &mut encoder,
+                    texture,
+                    size,
+                    target.encoding,
+                    graph.color_space,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(timer) = &timer {
+            timer.resolve(&mut encoder);
+        }
+
+        context.queue.submit(Some(encoder.finish()));
+        let rendered_nodes = commit_fresh(&mut self.cache, scheduled.fresh, graph, |texture| {
+            context.textures.release(texture)
+        });
+
+        let images = readbacks
+            .into_iter()
+            .map(|readback| readback.read(context))
+            .collect::<Result<Vec<_>, _>>();
+        drop(frame);
+        let images = images?;
+
+        let mut nodes = scheduled.nodes;
+        let timing = match timer {
+            Some(timer) => {
+                let durations = timer.read(context)?;
+                for node in &mut nodes {
+                    node.duration_ms = durations.get(&node.node_id).copied();
+                }
+                TimingSource::Gpu
+            }
+            None => TimingSource::Cpu,
+        };
+        let stats = RenderStats::new(
+            self.name(),
+            timing,
+            started.elapsed(),
+            nodes,
+            unique_bytes(self.cache.images(), texture_bytes),
+            context.textures.free_bytes(),
+        );
+        Ok(RenderResult {
+            images,
+            rendered_nodes,
+            stats,
+        })
+    }
+}
@@ -0,0 +1,294 @@
+//! Render backends and the schedule driver they share.
+//!
+//! [`gpu::GpuBackend`] renders through wgpu. [`cpu::CpuBackend`] renders the
+//! same node set in plain Rust, for machines without an adapter and as a
+//! readable reference for what the shaders are expected to produce.
+
+pub mod cpu;
+pub mod gpu;
+
+use crate::cache::{content_hash, NodeCache};
+use crate::color_space::ColorSpace;
+use crate::error::EngineError;
+use crate::graph::{Graph, Size};
+use crate::nodes::NodeRegistry;
+use crate::scheduler::{OutputKey, Schedule, Step};
+use crate::state::{OutputEncoding, RenderedImage};
+use crate::stats::{milliseconds, NodeStats, RenderStats};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Which backend `AppState::initialize` sets up.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BackendKind {
+    /// The GPU if an adapter is found, the CPU otherwise.
+    #[default]
+    Auto,
+    Gpu,
+    Cpu,
+}
+
+/// A node output to read back in `encoding`, optionally scaled down to fit
+/// `max_size`.
+#[derive(Debug, Clone)]
+pub struct ReadbackTarget {
+    pub key: OutputKey,
+    pub max_size: Option<u32>,
+    pub encoding: OutputEncoding,
+}
+
+/// Images read back for a set of targets, in target order, plus the ids of
+/// the nodes that had to be rendered because they were not in the cache.
+pub struct RenderResult {
+    pub images: Vec<RenderedImage>,
+    pub rendered_nodes: Vec<String>,
+    pub stats: RenderStats,
+}
+
+/// Follows a render while it runs. The unit type ignores everything.
+pub trait RenderMonitor {
+    /// Called after each schedule step with the number of steps run so far
+    /// and in total.
+    fn step_finished(&self, _finished: usize, _total: usize) {}
+
+    /// Whether the render is no longer wanted. Checked before each step.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl RenderMonitor for () {}
+
+pub trait Backend: Send {
+    /// Describes the backend for logs, e.g. with the adapter it runs on.
+    fn name(&self) -> String;
+
+    /// Why the backend's device was lost, if it was. Only GPU backends have
+    /// one to lose.
+    fn lost_device(&self) -> Option<String> {
+        None
+    }
+
+    /// Sets the backend up again from scratch, dropping everything it has
+    /// cached, e.g. after its device was lost.
+    fn reset(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    /// Renders everything `targets` depend on, reusing and refreshing the
+    /// backend's node cache, and reads each target back. Stops with
+    /// [`EngineError::Cancelled`] once `monitor` says so.
+    ///
+    /// Expects a graph that passed [`Graph::validate`].
+    fn render(
+        &mut self,
+        graph: &Graph,
+        registry: &NodeRegistry,
+        targets: &[ReadbackTarget],
+        monitor: &dyn RenderMonitor,
+    ) -> Result<RenderResult, EngineError>;
+}
+
+/// Outputs of a node rendered during this run, with the content hash they
+/// belong under in the cache.
+pub struct Fresh<T> {
+    pub node_id: String,
+    pub hash: u64,
+    pub outputs: HashMap<String, T>,
+}
+
+/// What [`run_schedule`] did: the nodes it rendered fresh, plus stats for
+/// every step, timed on the CPU.
+pub struct Scheduled<T> {
+    pub fresh: Vec<Fresh<T>>,
+    pub nodes: Vec<NodeStats>,
+}
+
+/// Runs schedule steps on one backend and holds the outputs of the steps
+/// run so far.
+pub trait StepEvaluator {
+    type Image: Clone;
+
+    /// Size of nodes that neither override it nor have an input to follow.
+    fn canvas(&self) -> Size;
+
+    /// The working space of the graph being rendered.
+    fn color_space(&self) -> ColorSpace;
+
+    /// The processors steps are evaluated with.
+    fn registry(&self) -> &NodeRegistry;
+
+    fn output(&self, key: &OutputKey) -> Option<&Self::Image>;
+
+    fn insert_output(&mut self, key: OutputKey, image: Self::Image);
+
+    fn remove_output(&mut self, key: &OutputKey) -> Option<Self::Image>;
+
+    /// Called with images the render no longer needs, so backends that
+    /// recycle them can take them back.
+    fn release(&mut self, image: Self::Image) {
+        drop(image);
+    }
+
+    fn image_size(image: &Self::Image) -> Size;
+
+    /// Renders one node at `size` and returns its outputs by handle.
+    fn evaluate(
+        &mut self,
+        step: &Step,
+        size: Size,
+    ) -> Result<HashMap<String, Self::Image>, EngineError>;
+
+    /// Works out the output size of a step: its own override, else the size
+    /// of what it reads from outside the graph, else the size of its first
+    /// connected input by handle name, else the canvas.
+    fn resolve_size(&self, step: &Step, external: Option<Size>) -> Size {
+        if let Some(size) = step.node.size.or(external) {
+            return size;
+        }
+        let mut handles: Vec<&String> = step.inputs.keys().collect();
+        handles.sort();
+        handles
+            .into_iter()
+            .find_map(|handle| self.output(&step.inputs[handle]))
+            .map(Self::image_size)
+            .unwrap_or(self.canvas())
+    }
+}
+
+/// Runs every step of `schedule`, taking nodes whose content hash is
+/// unchanged from `cache`.
+///
+/// Outputs other than `targets` are released as soon as the last step
+/// reading them has run. Fresh outputs are left for the caller to add to the
+/// cache once the backend's work has completed, so a failed render cannot
+/// leave unrendered images there.
+pub fn run_schedule<E: StepEvaluator>(
+    evaluator: &mut E,
+    schedule: &Schedule,
+    cache: &NodeCache<E::Image>,
+    targets: &[ReadbackTarget],
+    monitor: &dyn RenderMonitor,
+) -> Result<Scheduled<E::Image>, EngineError> {
+    let kept: HashSet<&OutputKey> = targets.iter().map(|t| &t.key).collect();
+    let mut last_use: HashMap<&OutputKey, usize> = HashMap::new();
+    for (index, step) in schedule.steps.iter().enumerate() {
+        for key in step.inputs.values() {
+            last_use.insert(key, index);
+        }
+    }
+
+    let mut hashes = HashMap::new();
+    let mut fresh = Vec::new();
+    let mut nodes = Vec::new();
+    for (index, step) in schedule.steps.iter().enumerate() {
+        if monitor.is_cancelled() {
+            return Err(EngineError::cancelled());
+        }
+        let node_id = &step.node.id;
+        let external = match evaluator.registry().get(step.node.kind.type_name()) {
+            Some(processor) => processor.external(step.node)?,
+            None => None,
+        };
+        let size = evaluator.resolve_size(step, external.map(|e| e.size));
+        let hash = content_hash(
+            step,
+            size,
+            evaluator.color_space(),
+            external.map(|e| e.version),
+            &hashes,
+        );
+        hashes.insert(node_id.clone(), hash);
+
+        let cached = cache.get(node_id, hash);
+        let mut duration_ms = None;
+        let outputs = match cached {
+            Some(outputs) => outputs.clone(),
+            None => {
+                let started = Instant::now();
+                let outputs = evaluator.evaluate(step, size)?;
+                duration_ms = Some(milliseconds(started.elapsed()));
+                fresh.push(Fresh {
+                    node_id: node_id.clone(),
+                    hash,
+                    outputs: outputs.clone(),
+                });
+                outputs
+            }
+        };
+        nodes.push(NodeStats {
+            node_id: node_id.clone(),
+            node_type: step.node.kind.type_name().to_string(),
+            cached: cached.is_some(),
+            duration_ms,
+        });
+        for (handle, image) in outputs {
+            let key = OutputKey::new(node_id, &handle);
+            if kept.contains(&key) || last_use.contains_key(&key) {
+                evaluator.insert_output(key, image);
+            } else {
+                evaluator.release(image);
+            }
+        }
+
+        for key in step.inputs.values() {
+            if last_use.get(key) == Some(&index) && !kept.contains(key) {
+                if let Some(image) = evaluator.remove_output(key) {
+                    evaluator.release(image);
+                }
+            }
+        }
+        monitor.step_finished(index + 1, schedule.steps.len());
+    }
+    Ok(Scheduled { fresh, nodes })
+}
+
+/// Looks up the image of every target after [`run_schedule`].
+pub fn target_images<E: StepEvaluator>(
+    evaluator: &E,
+    targets: &[ReadbackTarget],
+) -> Result<Vec<E::Image>, EngineError> {
+    targets
+        .iter()
+        .map(|target| {
+            evaluator.output(&target.key).cloned().ok_or_else(|| {
+                EngineError::node_handle(
+                    &target.key.node_id,
+                    &target.key.handle,
+                    "Output was not produced",
+                )
+            })
+        })
+        .collect()
+}
+
+/// Moves freshly rendered outputs into `cache` and drops entries of nodes
+/// that left the graph, passing every image the cache lets go of to
+/// `release`. Returns the ids of the fresh nodes.
+pub fn commit_fresh<T>(
+    cache: &mut NodeCache<T>,
+    fresh: Vec<Fresh<T>>,
+    graph: &Graph,
+    mut release: impl FnMut(T),
+) -> Vec<String> {
+    let rendered_nodes = fresh.iter().map(|f| f.node_id.clone()).collect();
+    let mut evicted = Vec::new();
+    for f in fresh {
+        evicted.extend(cache.insert(f.node_id, f.hash, f.outputs));
+    }
+    evicted.extend(cache.retain_graph(graph));
+    for outputs in evicted {
+        outputs.into_values().for_each(&mut release);
+    }
+    rendered_nodes
+}
+
+/// The size a target is read back at.
+pub fn readback_size(target: &ReadbackTarget, size: Size) -> Size {
+    match target.max_size {
+        Some(max_size) => size.fit_within(max_size),
+        None => size,
+    }
+}
@@ -0,0 +1,168 @@
+use crate::color_space::ColorSpace;
+use crate::graph::{Graph, Size};
+use crate::scheduler::Step;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+struct CacheEntry<T> {
+    hash: u64,
+    outputs: HashMap<String, T>,
+}
+
+/// Node output textures kept between renders.
+///
+/// Each entry is tagged with the node's content hash, which covers the node's
+/// parameters and the hashes of everything wired into it. Editing a node
+/// changes its hash and, through the upstream hashes, the hash of every node
+/// downstream of it, so exactly the dirty part of the graph misses the cache.
+///
+/// `T` is the backend's image type, e.g. a GPU texture.
+pub struct NodeCache<T> {
+    entries: HashMap<String, CacheEntry<T>>,
+}
+
+impl<T> Default for NodeCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> NodeCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the outputs stored for `node_id` if they were rendered from
+    /// the same content hash.
+    pub fn get(&self, node_id: &str, hash: u64) -> Option<&HashMap<String, T>> {
+        self.entries
+            .get(node_id)
+            .filter(|entry| entry.hash == hash)
+            .map(|entry| &entry.outputs)
+    }
+
+    /// Stores the outputs of `node_id`, returning the ones they replace.
+    pub fn insert(
+        &mut self,
+        node_id: String,
+        hash: u64,
+        outputs: HashMap<String, T>,
+    ) -> Option<HashMap<String, T>> {
+        self.entries
+            .insert(node_id, CacheEntry { hash, outputs })
+            .map(|entry| entry.outputs)
+    }
+
+    /// Every output held, for accounting.
+    pub fn images(&self) -> impl Iterator<Item = &T> {
+        self.entries
+            .values()
+            .flat_map(|entry| entry.outputs.values())
+    }
+
+    /// Drops the entries of nodes that are no longer part of `graph` and
+    /// returns their outputs.
+    pub fn retain_graph(&mut self, graph: &Graph) -> Vec<HashMap<String, T>> {
+        let removed: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|node_id| graph.get_node(node_id).is_none())
+            .cloned()
+            .collect();
+        removed
+            .iter()
+            .filter_map(|node_id| self.entries.remove(node_id))
+            .map(|entry| entry.outputs)
+            .collect()
+    }
+}
+
+/// Hashes a step's node type, parameters, sampler settings, resolved output
+/// size, working color space, external source version and inputs.
+/// `upstream` must already hold the hash of every node the step reads from.
+pub fn content_hash(
+    step: &Step,
+    size: Size,
+    space: ColorSpace,
+    version: Option<u64>,
+    upstream: &HashMap<String, u64>,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    size.hash(&mut hasher);
+    space.hash(&mut hasher);
+    version.hash(&mut hasher);
+
+    // Parameters are hashed through their serialized form so that new node
+    // types are covered without writing a `Hash` impl for their floats.
+    serde_json::to_string(&step.node.kind)
+        .expect("node parameters serialize to JSON")
+        .hash(&mut hasher);
+    serde_json::to_string(&step.node.samplers)
+        .expect("sampler settings serialize to JSON")
+        .hash(&mut hasher);
+
+    let mut inputs: Vec<_> = step.inputs.iter().collect();
+    inputs.sort_by(|a, b| a.0.cmp(b.0));
+    for (handle, key) in inputs {
+        handle.hash(&mut hasher);
+        key.handle.hash(&mut hasher);
+        upstream.get(&key.node_id).hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::two_colors_mixed;
+    use crate::scheduler::Schedule;
+
+    /// The content hash of every node the output needs.
+    fn hashes(graph: &Graph) -> HashMap<String, u64> {
+        let mut hashes = HashMap::new();
+        for step in &Schedule::build(graph, &["out"]).unwrap().steps {
+            let hash = content_hash(step, Size::new(8, 8), ColorSpace::Linear, None, &hashes);
+            hashes.insert(step.node.id.clone(), hash);
+        }
+        hashes
+    }
+
+    /// The nodes whose hash differs between `before` and `after`.
+    fn dirty(before: &HashMap<String, u64>, after: &HashMap<String, u64>) -> Vec<String> {
+        let mut ids: Vec<String> = after
+            .iter()
+            .filter(|(id, hash)| before.get(*id) != Some(hash))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn only_edited_nodes_and_their_dependents_are_dirty() {
+        let first = hashes(&two_colors_mixed(255));
+        assert!(dirty(&first, &hashes(&two_colors_mixed(255))).is_empty());
+        assert_eq!(
+            dirty(&first, &hashes(&two_colors_mixed(128))),
+            ["blue", "mix", "out"]
+        );
+    }
+
+    #[test]
+    fn entries_are_only_returned_for_the_same_hash() {
+        let graph = two_colors_mixed(255);
+        let mut cache = NodeCache::<()>::new();
+        cache.insert("mix".to_string(), 1, HashMap::new());
+        assert!(cache.get("mix", 1).is_some());
+        assert!(cache.get("mix", 2).is_none());
+
+        cache.insert("gone".to_string(), 1, HashMap::new());
+        cache.retain_graph(&graph);
+        assert!(cache.get("mix", 1).is_some());
+        assert!(cache.get("gone", 1).is_none());
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// The space node outputs are stored and blended in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorSpace {
+    /// Linear light in half floats. sRGB colors are decoded on the way in
+    /// and encoded again by the output stage, so blends are physically
+    /// plausible.
+    #[default]
+    Linear,
+    /// sRGB values as they are, in 8 bits. Blends come out darker in the
+    /// middle, the classic look of paint programs and pixel-art tools.
+    Gamma,
+}
+
+impl ColorSpace {
+    /// The format of node output textures in this space.
+    pub fn format(self) -> wgpu::TextureFormat {
+        match self {
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba16Float,
+            ColorSpace::Gamma => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    /// Converts an sRGB-encoded color channel into this space.
+    pub fn from_srgb(self, value: f32) -> f32 {
+        match self {
+            ColorSpace::Linear => srgb_to_linear(value),
+            ColorSpace::Gamma => value,
+        }
+    }
+}
+
+/// The sRGB transfer function, as the GPU applies it when sampling an
+/// `*Srgb` texture.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The inverse sRGB transfer function, as the GPU applies it when writing to
+/// an `*Srgb` target.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,126 @@
+use crate::validation::Diagnostic;
+use serde::Serialize;
+use std::fmt;
+
+/// Everything that can go wrong between receiving a graph and returning its
+/// pixels. Serialized with a `kind` tag so the frontend can tell the cases
+/// apart and point at the node involved.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum EngineError {
+    /// The graph could not be parsed or failed validation. Parse errors and
+    /// bad requests carry no diagnostics.
+    #[serde(rename_all = "camelCase")]
+    Validation {
+        message: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// A node failed to render. `handle` names the input or output involved,
+    /// when the failure is tied to one.
+    #[serde(rename_all = "camelCase")]
+    NodeEvaluation {
+        node_id: String,
+        handle: Option<String>,
+        message: String,
+    },
+    /// No usable adapter or device, or the GPU failed outside of any node.
+    Gpu {
+        message: String,
+    },
+    Io {
+        message: String,
+    },
+    /// A newer render of the same kind was requested before this one
+    /// finished. Not a failure; the newer render carries the result.
+    Cancelled {
+        message: String,
+    },
+}
+
+impl EngineError {
+    /// A validation error without diagnostics, for requests that do not
+    /// make sense for the graph.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        EngineError::Validation {
+            message: message.into(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn diagnostics(diagnostics: Vec<Diagnostic>) -> Self {
+        let message = diagnostics
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        EngineError::Validation {
+            message,
+            diagnostics,
+        }
+    }
+
+    pub fn node(node_id: &str, message: impl Into<String>) -> Self {
+        EngineError::NodeEvaluation {
+            node_id: node_id.to_string(),
+            handle: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn node_handle(node_id: &str, handle: &str, message: impl Into<String>) -> Self {
+        EngineError::NodeEvaluation {
+            node_id: node_id.to_string(),
+            handle: Some(handle.to_string()),
+            message: message.into(),
+        }
+    }
+
+    pub fn gpu(message: impl Into<String>) -> Self {
+        EngineError::Gpu {
+            message: message.into(),
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        EngineError::Cancelled {
+            message: "Superseded by a newer render".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Validation { message, .. } => write!(f, "invalid graph: {}", message),
+            EngineError::NodeEvaluation {
+                node_id,
+                handle: Some(handle),
+                message,
+            } => write!(f, "node '{}' ({}): {}", node_id, handle, message),
+            EngineError::NodeEvaluation {
+                node_id,
+                handle: None,
+                message,
+            } => write!(f, "node '{}': {}", node_id, message),
+            EngineError::Gpu { message } => write!(f, "GPU error: {}", message),
+            EngineError::Io { message } => write!(f, "I/O error: {}", message),
+            EngineError::Cancelled { message } => write!(f, "cancelled: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<std::io::Error> for EngineError {
+    fn from(error: std::io::Error) -> Self {
+        EngineError::Io {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for EngineError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        EngineError::gpu("render state is unavailable after an earlier render panicked")
+    }
+}
@@ -0,0 +1,182 @@
+use crate::backend::StepEvaluator;
+use crate::color_space::ColorSpace;
+use crate::error::EngineError;
+use crate::graph::Size;
+use crate::nodes::{NodeContext, NodeRegistry, Outputs};
+use crate::scheduler::{OutputKey, Step};
+use crate::state::RenderContext;
+use crate::textures::TextureKey;
+use crate::timestamps::NodeTimer;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Records the GPU work for individual schedule steps into one command encoder.
+pub struct Evaluator<'a> {
+    pub context: &'a RenderContext,
+    pub registry: &'a NodeRegistry,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// Output table for this render, keyed by node id and output handle.
+    pub outputs: HashMap<OutputKey, Arc<wgpu::Texture>>,
+    /// Size of nodes that neither override it nor have an input to follow.
+    pub canvas: Size,
+    /// Decides the format of every texture created for node outputs.
+    pub color_space: ColorSpace,
+    /// Textures the current step only needs while it runs, such as black
+    /// defaults for unconnected inputs, which go back to the pool once it
+    /// has run.
+    pub transient: Vec<Arc<wgpu::Texture>>,
+    /// Times each node's passes where the device supports timestamps.
+    pub timer: Option<NodeTimer>,
+}
+
+impl<'a> Evaluator<'a> {
+    /// Allocates a node output texture, which can be drawn into by render
+    /// passes and written by compute passes alike.
+    pub fn create_texture(
+        &mut self,
+        label: &str,
+        size: Size,
+    ) -> Result<Arc<wgpu::Texture>, String> {
+        let max = self.context.device.limits().max_texture_dimension_2d;
+        if size.width > max || size.height > max {
+            return Err(format!(
+                "{} is {}x{}, larger than the device limit of {}",
+                label, size.width, size.height, max
+            ));
+        }
+
+        Ok(self.context.textures.acquire(
+            &self.context.device,
+            label,
+            TextureKey {
+                size,
+                format: self.color_space.format(),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+            },
+        ))
+    }
+
+    /// Looks up the texture wired into `handle`, or black at `size` if nothing
+    /// is connected.
+    pub fn input(
+        &mut self,
+        step: &Step,
+        handle: &str,
+        size: Size,
+    ) -> Result<Arc<wgpu::Texture>, String> {
+        match step.input(handle) {
+            Some(key) => self.outputs.get(key).cloned().ok_or_else(|| {
+                format!(
+                    "Output '{}' of node {} has not been rendered",
+                    key.handle, key.node_id
+                )
+            }),
+            None => self.create_black_texture(size),
+        }
+    }
+
+    /// Records a pass clearing `texture` to `color`.
+    pub fn clear(&mut self, texture: &wgpu::Texture, color: wgpu::Color) {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let _pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+    }
+
+    fn create_black_texture(&mut self, size: Size) -> Result<Arc<wgpu::Texture>, String> {
+        let texture = self.create_texture("Black Default", size)?;
+        self.clear(&texture, wgpu::Color::BLACK);
+        self.transient.push(texture.clone());
+        Ok(texture)
+    }
+}
+
+impl StepEvaluator for Evaluator<'_> {
+    type Image = Arc<wgpu::Texture>;
+
+    fn canvas(&self) -> Size {
+        self.canvas
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn registry(&self) -> &NodeRegistry {
+        self.registry
+    }
+
+    fn output(&self, key: &OutputKey) -> Option<&Self::Image> {
+        self.outputs.get(key)
+    }
+
+    fn insert_output(&mut self, key: OutputKey, image: Self::Image) {
+        self.outputs.insert(key, image);
+    }
+
+    fn remove_output(&mut self, key: &OutputKey) -> Option<Self::Image> {
+        self.outputs.remove(key)
+    }
+
+    fn release(&mut self, image: Self::Image) {
+        self.context.textures.release(image);
+    }
+
+    fn image_size(image: &Self::Image) -> Size {
+        Size::new(image.width(), image.height())
+    }
+
+    /// Renders one node through the processor registered for its type. wgpu
+    /// errors raised meanwhile, such as validation errors from a pass, are
+    /// reported as errors of the node.
+    fn evaluate(&mut self, step: &Step, size: Size) -> Result<Outputs, EngineError> {
+        let node_type = step.node.kind.type_name();
+        let registry = self.registry;
+        let processor = registry.get(node_type).ok_or_else(|| {
+            EngineError::node(
+                &step.node.id,
+                format!("No processor registered for {}", node_type),
+            )
+        })?;
+
+        let device = &self.context.device;
+        device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        if let Some(timer) = &mut self.timer {
+            timer.begin(self.encoder);
+        }
+        let mut ctx = NodeContext {
+            evaluator: self,
+            step,
+            size,
+        };
+        let outputs = processor.process(&mut ctx);
+        if let Some(timer) = &mut self.timer {
+            timer.end(self.encoder, &step.node.id);
+        }
+        let validation = pollster::block_on(device.pop_error_scope());
+        let out_of_memory = pollster::block_on(device.pop_error_scope());
+
+        for texture in std::mem::take(&mut self.transient) {
+            self.context.textures.release(texture);
+        }
+        match validation.or(out_of_memory) {
+            Some(error) => Err(EngineError::node(&step.node.id, error.to_string())),
+            None => outputs,
+        }
+    }
+}
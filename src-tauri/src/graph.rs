@@ -1,6 +1,20 @@
-use serde::Deserialize;
+use crate::color_space::ColorSpace;
+use crate::error::EngineError;
+use crate::nodes::color::ColorParams;
+use crate::nodes::dilate::DilateParams;
+use crate::nodes::gradient::GradientParams;
+use crate::nodes::image_source::ImageSourceParams;
+use crate::nodes::mix::MixParams;
+use crate::nodes::noise::NoiseParams;
+use crate::nodes::output::OutputParams;
+use crate::nodes::pattern::PatternParams;
+use crate::nodes::shape::ShapeParams;
+use crate::nodes::split::SplitParams;
+use crate::samplers::SamplerSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ColorData {
     pub r: u8,
     pub g: u8,
@@ -8,19 +22,276 @@ pub struct ColorData {
     pub a: f32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct NodeData {
-    pub label: String,
-    pub color: Option<ColorData>,
-    pub factor: Option<f32>,
+impl Default for ColorData {
+    fn default() -> Self {
+        Self {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 1.0,
+        }
+    }
+}
+
+impl ColorData {
+    /// The color as RGBA channels in `0.0..=1.0`, with the 8-bit sRGB
+    /// channels converted into `space`.
+    pub fn to_rgba(&self, space: ColorSpace) -> [f32; 4] {
+        let channel = |c: u8| space.from_srgb(c as f32 / 255.0);
+        [channel(self.r), channel(self.g), channel(self.b), self.a]
+    }
+}
+
+/// A color at a position along a gradient.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ColorStop {
+    /// Where the stop sits, from 0.0 at the start of the gradient to 1.0 at
+    /// its end.
+    pub position: f32,
+    pub color: ColorData,
+}
+
+/// A color ramp: a list of stops, blended between neighbouring stops and
+/// held flat before the first and after the last. Stops may come in any
+/// order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct Gradient {
+    pub stops: Vec<ColorStop>,
+}
+
+impl Default for Gradient {
+    /// Black to white.
+    fn default() -> Self {
+        let stop = |position: f32, value: u8| ColorStop {
+            position,
+            color: ColorData {
+                r: value,
+                g: value,
+                b: value,
+                a: 1.0,
+            },
+        };
+        Self {
+            stops: vec![stop(0.0, 0), stop(1.0, 255)],
+        }
+    }
+}
+
+impl Gradient {
+    /// The stops sorted by position, with colors converted into `space`.
+    pub fn to_rgba(&self, space: ColorSpace) -> Vec<(f32, [f32; 4])> {
+        let mut stops: Vec<_> = self
+            .stops
+            .iter()
+            .map(|stop| (stop.position, stop.color.to_rgba(space)))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        stops
+    }
+}
+
+/// Texture dimensions in pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Size {
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn extent(self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    /// Whether either side is above [`MAX_SIZE`].
+    pub fn too_large(self) -> bool {
+        self.width > MAX_SIZE || self.height > MAX_SIZE
+    }
+
+    /// Scales down to fit within `max` × `max`, keeping the aspect ratio.
+    /// Sizes that already fit are returned unchanged.
+    pub fn fit_within(self, max: u32) -> Self {
+        let longest = self.width.max(self.height);
+        if longest <= max {
+            return self;
+        }
+        let scale = |side: u32| {
+            ((side as u64 * max as u64 + longest as u64 / 2) / longest as u64).max(1) as u32
+        };
+        Self::new(scale(self.width), scale(self.height))
+    }
+}
+
+/// A pixel position on the canvas, from the top left.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Canvas size used when a graph does not specify one.
+pub const DEFAULT_CANVAS: Size = Size::new(256, 256);
+
+/// The largest width or height the canvas or a node may have: the texture
+/// dimension wgpu guarantees on every desktop adapter. The GPU backend asks
+/// the device for it, and the CPU backend keeps to it too, so a graph renders
+/// on either or on neither.
+pub const MAX_SIZE: u32 = 8192;
+
+/// Declares [`NodeKind`] from one line per node type, so its React Flow
+/// `type`, the serde tag and [`NodeKind::type_name`] cannot drift apart.
+macro_rules! node_kinds {
+    ($($variant:ident($params:ty) = $type_name:literal,)*) => {
+        /// The node type together with its parameters, as sent by React Flow
+        /// in the node's `type` and `data` fields.
+        #[derive(Serialize, Deserialize, Debug, Clone)]
+        #[serde(tag = "type", content = "data")]
+        pub enum NodeKind {
+            $(
+                #[serde(rename = $type_name)]
+                $variant($params),
+            )*
+        }
+
+        impl NodeKind {
+            /// Every node type, in declaration order.
+            #[cfg(test)]
+            pub const TYPE_NAMES: &'static [&'static str] = &[$($type_name),*];
+
+            /// The React Flow `type` of this node, which the processor
+            /// registry is keyed by.
+            pub fn type_name(&self) -> &'static str {
+                match self {
+                    $(NodeKind::$variant(_) => $type_name,)*
+                }
+            }
+        }
+    };
+}
+
+node_kinds! {
+    Color(ColorParams) = "colorNode",
+    Dilate(DilateParams) = "dilateNode",
+    Gradient(GradientParams) = "gradientNode",
+    ImageSource(ImageSourceParams) = "imageNode",
+    Mix(MixParams) = "mixNode",
+    Noise(NoiseParams) = "noiseNode",
+    Output(OutputParams) = "outputNode",
+    Pattern(PatternParams) = "patternNode",
+    Shape(ShapeParams) = "shapeNode",
+    Split(SplitParams) = "splitNode",
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "RawNode")]
 pub struct Node {
     pub id: String,
+    pub label: String,
+    /// Output size override. Without one a node takes the size of its first
+    /// connected input, or the canvas size if it has none.
+    pub size: Option<Size>,
+    /// Sampler settings by input handle. Inputs without any use
+    /// [`SamplerSettings::default`].
+    pub samplers: BTreeMap<String, SamplerSettings>,
+    pub kind: NodeKind,
+}
+
+impl Node {
+    /// How input `handle` is sampled.
+    pub fn sampler(&self, handle: &str) -> SamplerSettings {
+        self.samplers.get(handle).copied().unwrap_or_default()
+    }
+}
+
+/// Node as it arrives from the frontend, before its `data` is checked against
+/// the parameters of its type.
+#[derive(Deserialize)]
+struct RawNode {
+    id: String,
     #[serde(rename = "type")]
-    pub node_type: String, // "colorNode" or "outputNode"
-    pub data: NodeData,
+    node_type: String,
+    #[serde(default)]
+    data: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TryFrom<RawNode> for Node {
+    type Error = String;
+
+    fn try_from(raw: RawNode) -> Result<Self, Self::Error> {
+        let mut data = raw.data;
+        // The label, size and samplers are shared by every node type, so they
+        // live on `Node` itself.
+        let label = match data.remove("label") {
+            Some(serde_json::Value::String(label)) => label,
+            Some(other) => {
+                return Err(format!(
+                    "node '{}': invalid `data.label`: expected a string, got {}",
+                    raw.id, other
+                ))
+            }
+            None => String::new(),
+        };
+        let size = data
+            .remove("size")
+            .filter(|size| !size.is_null())
+            .map(|size| {
+                serde_path_to_error::deserialize(size).map_err(|e| {
+                    format!(
+                        "node '{}': invalid `data.size.{}`: {}",
+                        raw.id,
+                        e.path(),
+                        e.inner()
+                    )
+                })
+            })
+            .transpose()?;
+        let samplers = data
+            .remove("samplers")
+            .filter(|samplers| !samplers.is_null())
+            .map(|samplers| {
+                serde_path_to_error::deserialize(samplers).map_err(|e| {
+                    format!(
+                        "node '{}': invalid `data.samplers.{}`: {}",
+                        raw.id,
+                        e.path(),
+                        e.inner()
+                    )
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        // Feed `type` before `data` so serde can deserialize the parameters
+        // without buffering them, which keeps the path to a bad field intact.
+        let tagged = serde::de::value::MapDeserializer::<_, serde_json::Error>::new(
+            [
+                ("type", serde_json::Value::String(raw.node_type)),
+                ("data", serde_json::Value::Object(data)),
+            ]
+            .into_iter(),
+        );
+        let kind: NodeKind = serde_path_to_error::deserialize(tagged)
+            .map_err(|e| format!("node '{}': invalid `{}`: {}", raw.id, e.path(), e.inner()))?;
+
+        Ok(Self {
+            id: raw.id,
+            label,
+            size,
+            samplers,
+            kind,
+        })
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -34,14 +305,140 @@ pub struct Edge {
     pub target_handle: Option<String>,
 }
 
+/// Handle used for an edge end that React Flow leaves unnamed, which it does
+/// for nodes with a single output.
+pub const DEFAULT_OUTPUT: &str = "out";
+/// Handle used for an edge end targeting a node with a single unnamed input.
+pub const DEFAULT_INPUT: &str = "in";
+
+impl Edge {
+    pub fn output_handle(&self) -> &str {
+        self.source_handle.as_deref().unwrap_or(DEFAULT_OUTPUT)
+    }
+
+    pub fn input_handle(&self) -> &str {
+        self.target_handle.as_deref().unwrap_or(DEFAULT_INPUT)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Graph {
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
+    #[serde(default = "Graph::default_canvas")]
+    pub canvas: Size,
+    /// The working space every node of the graph renders in.
+    #[serde(default, rename = "colorSpace")]
+    pub color_space: ColorSpace,
 }
 
 impl Graph {
+    /// Parses a graph in the JSON format the frontend sends.
+    pub fn from_json(json: &str) -> Result<Self, EngineError> {
+        serde_json::from_str(json).map_err(|e| EngineError::invalid(e.to_string()))
+    }
+
+    fn default_canvas() -> Size {
+        DEFAULT_CANVAS
+    }
+
     pub fn get_node(&self, id: &str) -> Option<&Node> {
         self.nodes.iter().find(|n| n.id == id)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// A graph of `nodes` as `(id, type, data)` and `edges` as
+    /// `(id, source, target, target handle)`, on the default canvas.
+    pub(crate) fn graph_from(
+        nodes: &[(&str, &str, Value)],
+        edges: &[(&str, &str, &str, Option<&str>)],
+    ) -> Graph {
+        serde_json::from_value(json!({
+            "nodes": nodes
+                .iter()
+                .map(|(id, kind, data)| json!({ "id": id, "type": kind, "data": data }))
+                .collect::<Vec<_>>(),
+            "edges": edges
+                .iter()
+                .map(|(id, source, target, handle)| {
+                    json!({
+                        "id": id,
+                        "source": source,
+                        "target": target,
+                        "targetHandle": handle,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+    /// Red and a blue of `blue` mixed into the output on an 8x8 canvas,
+    /// plus a color nothing reads.
+    pub(crate) fn two_colors_mixed(blue: u8) -> Graph {
+        let mut graph = graph_from(
+            &[
+                (
+                    "red",
+                    "colorNode",
+                    json!({ "color": { "r": 255, "g": 0, "b": 0, "a": 1 } }),
+                ),
+                (
+                    "blue",
+                    "colorNode",
+                    json!({ "color": { "r": 0, "g": 0, "b": blue, "a": 1 } }),
+                ),
+                ("unused", "colorNode", json!({})),
+                ("mix", "mixNode", json!({ "factor": 0.5 })),
+                ("out", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "red", "mix", Some("a")),
+                ("e2", "blue", "mix", Some("b")),
+                ("e3", "mix", "out", None),
+            ],
+        );
+        graph.canvas = Size::new(8, 8);
+        graph
+    }
+
+    /// The message `from_json` gives for a graph of the single node `node`.
+    fn node_error(node: Value) -> String {
+        let json = json!({ "nodes": [node], "edges": [] }).to_string();
+        Graph::from_json(&json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn bad_nodes_are_reported_with_their_id_and_the_field() {
+        for (node, path) in [
+            (
+                json!({ "id": "mix", "type": "mixNode", "data": { "factr": 0.5 } }),
+                "`data.factr`",
+            ),
+            (
+                json!({ "id": "mix", "type": "mixNode", "data": { "factor": "x" } }),
+                "`data.factor`",
+            ),
+            (
+                json!({ "id": "mix", "type": "blurNode", "data": {} }),
+                "`type`",
+            ),
+            (
+                json!({
+                    "id": "mix",
+                    "type": "gradientNode",
+                    "data": { "stops": [{ "position": 0.5, "color": { "r": 1 } }] },
+                }),
+                "`data.stops[0].color`",
+            ),
+        ] {
+            let message = node_error(node);
+            assert!(message.contains("node 'mix'"), "{}", message);
+            assert!(message.contains(path), "{}", message);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Rendering without the Tauri app, for tests and tooling.
+
+use crate::backend::BackendKind;
+use crate::error::EngineError;
+use crate::graph::Graph;
+use crate::state::{AppState, RenderedImage};
+
+/// Renders the output node of a graph given in the JSON format `sync_graph`
+/// accepts, on a fresh backend of `kind`.
+pub fn render_graph_json(
+    graph_json: &str,
+    kind: BackendKind,
+) -> Result<RenderedImage, EngineError> {
+    let graph = Graph::from_json(graph_json)?;
+    let state = AppState::new();
+    pollster::block_on(async {
+        state.initialize(kind).await?;
+        Ok(state.render(graph).await?.image)
+    })
+}
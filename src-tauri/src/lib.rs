@@ -1,7 +1,29 @@
+mod backend;
+mod cache;
+mod color_space;
+mod error;
+mod evaluator;
 mod graph;
+pub mod headless;
+mod nodes;
+mod pipelines;
+mod readback;
+mod renderer;
+mod samplers;
+mod scheduler;
 mod shaders;
 mod state;
-use state::AppState;
+mod stats;
+mod textures;
+mod timestamps;
+mod validation;
+pub use backend::BackendKind;
+pub use color_space::ColorSpace;
+pub use error::EngineError;
+use state::{AppState, NodePreview};
+pub use state::{OutputEncoding, RenderedImage};
+use stats::RenderStats;
+use tauri::{Emitter, Manager};
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -9,30 +31,96 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-async fn init_wgpu(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    state.initialize().await
+async fn init_backend(
+    state: tauri::State<'_, AppState>,
+    backend: Option<BackendKind>,
+) -> Result<String, EngineError> {
+    state.initialize(backend.unwrap_or_default()).await
+}
+
+#[tauri::command]
+fn get_node_types(state: tauri::State<'_, AppState>) -> Vec<nodes::NodeTypeInfo> {
+    state.registry.infos()
 }
 
 #[tauri::command]
 async fn sync_graph(
     state: tauri::State<'_, AppState>,
     graph_json: String,
-) -> Result<Vec<u8>, String> {
-    let graph: graph::Graph = serde_json::from_str(&graph_json).map_err(|e| e.to_string())?;
+) -> Result<RenderedImage, EngineError> {
+    let graph = graph::Graph::from_json(&graph_json)?;
+    state.sync(graph.clone())?;
+    let output = state.render(graph).await?;
+    Ok(output.image)
+}
 
-    println!("Received graph with {} nodes", graph.nodes.len());
+/// Clones the graph last sent with `sync_graph`.
+fn synced_graph(state: &AppState) -> Result<graph::Graph, EngineError> {
+    state
+        .graph
+        .lock()?
+        .clone()
+        .ok_or_else(|| EngineError::invalid("No graph has been synced yet"))
+}
 
-    let result_bytes = state.render(graph).await?;
+#[tauri::command]
+async fn render_node(
+    state: tauri::State<'_, AppState>,
+    node_id: String,
+    handle: Option<String>,
+    encoding: Option<OutputEncoding>,
+) -> Result<RenderedImage, EngineError> {
+    let graph = synced_graph(&state)?;
+    let handle = handle.as_deref().unwrap_or(graph::DEFAULT_OUTPUT);
 
-    Ok(result_bytes)
+    let output = state
+        .render_node(&graph, &node_id, handle, encoding.unwrap_or_default())
+        .await?;
+    Ok(output.image)
+}
+
+#[tauri::command]
+async fn get_node_previews(
+    state: tauri::State<'_, AppState>,
+    max_size: u32,
+) -> Result<Vec<NodePreview>, EngineError> {
+    let graph = synced_graph(&state)?;
+    let output = state.render_previews(&graph, max_size).await?;
+    Ok(output.previews)
+}
+
+/// Returns timings, cache hits and texture memory of the last render.
+#[tauri::command]
+fn get_render_stats(state: tauri::State<'_, AppState>) -> Result<RenderStats, EngineError> {
+    state
+        .stats
+        .lock()?
+        .clone()
+        .ok_or_else(|| EngineError::invalid("No render has finished yet"))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(AppState::new())
-        .invoke_handler(tauri::generate_handler![greet, init_wgpu, sync_graph])
+        .setup(|app| {
+            let handle = app.handle().clone();
+            app.manage(AppState::with_events(Box::new(move |event| {
+                if let Err(e) = handle.emit(event.name(), &event) {
+                    eprintln!("Failed to emit {}: {}", event.name(), e);
+                }
+            })));
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            init_backend,
+            get_node_types,
+            sync_graph,
+            render_node,
+            get_node_previews,
+            get_render_stats
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,49 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef, ParamKind,
+};
+use crate::error::EngineError;
+use crate::graph::{ColorData, NodeKind};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ColorParams {
+    #[serde(default)]
+    pub color: ColorData,
+}
+
+/// Fills the node's output with one solid color.
+pub struct ColorProcessor;
+
+impl NodeProcessor for ColorProcessor {
+    fn node_type(&self) -> &'static str {
+        "colorNode"
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[ParamDef {
+            name: "color",
+            kind: ParamKind::Color,
+        }]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let NodeKind::Color(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a color node"));
+        };
+        let [r, g, b, a] = params.color.to_rgba(ctx.color_space()).map(f64::from);
+        let color = wgpu::Color { r, g, b, a };
+
+        let texture = ctx.create_texture(&format!("Color {}", ctx.node().label))?;
+        ctx.clear(&texture, color);
+        Ok(single_output(texture))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Color(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a color node"));
+        };
+        let color = params.color.to_rgba(ctx.color_space());
+        Ok(single_output(ctx.shade(|_| color)))
+    }
+}
@@ -0,0 +1,167 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef,
+    ParamKind, PortDef,
+};
+use crate::backend::cpu::{pixel_uv, CpuImage};
+use crate::error::EngineError;
+use crate::graph::{NodeKind, Size, DEFAULT_INPUT};
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DilateParams {
+    #[serde(default = "DilateParams::default_radius")]
+    pub radius: u32,
+}
+
+impl DilateParams {
+    fn default_radius() -> u32 {
+        1
+    }
+}
+
+/// Largest radius accepted, which bounds the number of passes.
+const MAX_RADIUS: u32 = 64;
+
+/// Grows bright areas of its input by `radius` pixels, one compute pass per
+/// pixel, taking the channel-wise maximum of each pixel and its four direct
+/// neighbours. Shapes grow into diamonds, like a hand-drawn pixel outline.
+pub struct DilateProcessor;
+
+impl NodeProcessor for DilateProcessor {
+    fn node_type(&self) -> &'static str {
+        "dilateNode"
+    }
+
+    fn inputs(&self) -> &'static [PortDef] {
+        &[PortDef {
+            handle: DEFAULT_INPUT,
+            label: "In",
+        }]
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[ParamDef {
+            name: "radius",
+            kind: ParamKind::Int {
+                min: 0,
+                max: MAX_RADIUS,
+                default: 1,
+            },
+        }]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let NodeKind::Dilate(params) = &node.kind else {
+            return Err(ctx.error("not a dilate node"));
+        };
+        let radius = params.radius;
+        if radius > MAX_RADIUS {
+            return Err(ctx.error(format!("Radius must be at most {}", MAX_RADIUS)));
+        }
+
+        let source = ctx.input(DEFAULT_INPUT)?;
+        // A zero radius passes the input through, resampled to the node's
+        // size if it differs.
+        let (entry_point, passes) = match radius {
+            0 if Size::new(source.width(), source.height()) == ctx.size => {
+                return Ok(single_output(source));
+            }
+            0 => ("cs_copy", 1),
+            _ => ("cs_main", radius),
+        };
+        let pipeline = ctx
+            .render_context()
+            .pipelines
+            .compute_pipeline(ctx.device(), &shaders::DILATE, entry_point, ctx.format())
+            .map_err(|e| ctx.error(e))?;
+
+        let texture = ctx.dispatch_ping_pong(
+            &format!("Dilate {}", node.label),
+            &pipeline,
+            source,
+            passes,
+            &[],
+        )?;
+        Ok(single_output(texture))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Dilate(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a dilate node"));
+        };
+        let radius = params.radius;
+        if radius > MAX_RADIUS {
+            return Err(ctx.error(format!("Radius must be at most {}", MAX_RADIUS)));
+        }
+
+        let size = ctx.size;
+        let mut image = ctx.input(DEFAULT_INPUT)?;
+        if radius == 0 && image.size != size {
+            let source = image;
+            image = Arc::new(CpuImage::from_fn(size, ctx.color_space(), |x, y| {
+                source.sample_nearest(pixel_uv(size, x, y))
+            }));
+        }
+        for _ in 0..radius {
+            let source = image;
+            // Neighbours are clamped to the output, then looked up in the
+            // source, which only differs in size on the first pass.
+            let fetch = |x: i64, y: i64| {
+                let x = x.clamp(0, size.width as i64 - 1) as u32;
+                let y = y.clamp(0, size.height as i64 - 1) as u32;
+                source.sample_nearest(pixel_uv(size, x, y))
+            };
+            image = Arc::new(CpuImage::from_fn(size, ctx.color_space(), |x, y| {
+                let (x, y) = (x as i64, y as i64);
+                [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                    .into_iter()
+                    .map(|(dx, dy)| fetch(x + dx, y + dy))
+                    .fold(fetch(x, y), |a, b| std::array::from_fn(|i| a[i].max(b[i])))
+            }));
+        }
+        Ok(single_output(image))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::cpu::CpuBackend;
+    use crate::backend::{Backend, ReadbackTarget};
+    use crate::graph::tests::graph_from;
+    use crate::graph::{Size, DEFAULT_OUTPUT};
+    use crate::nodes::NodeRegistry;
+    use crate::scheduler::OutputKey;
+    use crate::state::OutputEncoding;
+    use serde_json::json;
+
+    #[test]
+    fn zero_radius_still_takes_the_node_size() {
+        let mut graph = graph_from(
+            &[
+                ("red", "colorNode", json!({})),
+                (
+                    "grow",
+                    "dilateNode",
+                    json!({ "radius": 0, "size": { "width": 4, "height": 2 } }),
+                ),
+            ],
+            &[("e1", "red", "grow", None)],
+        );
+        graph.canvas = Size::new(8, 8);
+        let targets = [ReadbackTarget {
+            key: OutputKey::new("grow", DEFAULT_OUTPUT),
+            max_size: None,
+            encoding: OutputEncoding::Rgba8,
+        }];
+
+        let result = CpuBackend::new()
+            .render(&graph, &NodeRegistry::builtin(), &targets, &())
+            .unwrap();
+        let image = &result.images[0];
+        assert_eq!((image.width, image.height), (4, 2));
+    }
+}
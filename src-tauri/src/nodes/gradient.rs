@@ -0,0 +1,477 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef, ParamKind,
+};
+use crate::backend::cpu::{self, CpuImage};
+use crate::error::EngineError;
+use crate::graph::{Gradient, NodeKind, Point, Size};
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+/// How a pixel's position along the gradient is measured.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GradientKind {
+    /// Along the line from `from` to `to`.
+    #[default]
+    Linear,
+    /// Distance from `from`, reaching the end at `to`.
+    Radial,
+    /// Clockwise angle around `from`, starting in the direction of `to`.
+    Angular,
+    /// Like radial, but in the shape of a square turned towards `to`.
+    Diamond,
+}
+
+impl GradientKind {
+    /// The names the kinds are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["linear", "radial", "angular", "diamond"];
+}
+
+/// How colors between stops are filled in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Banding {
+    /// Blended smoothly.
+    #[default]
+    Smooth,
+    /// Quantized to `bands` flat bands, evenly spread over the ramp.
+    Bands,
+    /// Only the stop colors, mixed with a 4×4 ordered dither pattern.
+    Dither,
+}
+
+impl Banding {
+    /// The names the modes are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["smooth", "bands", "dither"];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GradientParams {
+    #[serde(default)]
+    pub gradient: GradientKind,
+    /// The pixel the gradient starts at, or its center.
+    #[serde(default)]
+    pub from: Point,
+    /// The pixel the gradient ends at. Defaults to the top right pixel, so
+    /// a linear gradient spans the width of the canvas.
+    #[serde(default)]
+    pub to: Option<Point>,
+    #[serde(default)]
+    pub stops: Gradient,
+    #[serde(default)]
+    pub banding: Banding,
+    #[serde(default = "GradientParams::default_bands")]
+    pub bands: u32,
+}
+
+impl GradientParams {
+    fn default_bands() -> u32 {
+        4
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.stops.stops.is_empty() {
+            return Err("A gradient must have at least one stop".to_string());
+        }
+        if self
+            .stops
+            .stops
+            .iter()
+            .any(|stop| !stop.position.is_finite())
+        {
+            return Err("Stop positions must be numbers".to_string());
+        }
+        if !(MIN_BANDS..=MAX_BANDS).contains(&self.bands) {
+            return Err(format!(
+                "Bands must be between {} and {}",
+                MIN_BANDS, MAX_BANDS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Where the gradient ends on a canvas of `size`.
+    fn end(&self, size: Size) -> Point {
+        self.to.unwrap_or(Point {
+            x: size.width.saturating_sub(1) as i32,
+            y: 0,
+        })
+    }
+
+    /// How far along the gradient ending at `to` the pixel centered at `p`
+    /// is, in `0.0..=1.0`.
+    fn position(&self, to: Point, p: [f32; 2]) -> f32 {
+        let from = center(self.from);
+        let d = sub(center(to), from);
+        let q = sub(p, from);
+        let length2 = dot(d, d);
+        if length2 == 0.0 {
+            return 0.0;
+        }
+        let t = match self.gradient {
+            GradientKind::Linear => dot(q, d) / length2,
+            GradientKind::Radial => (dot(q, q) / length2).sqrt(),
+            GradientKind::Angular => {
+                let angle = cross(d, q).atan2(dot(d, q)) / TAU;
+                if angle < 0.0 {
+                    angle + 1.0
+                } else {
+                    angle
+                }
+            }
+            GradientKind::Diamond => (dot(q, d).abs() + cross(d, q).abs()) / length2,
+        };
+        t.clamp(0.0, 1.0)
+    }
+
+    /// The color of pixel (`x`, `y`) in the gradient ending at `to`, from
+    /// `stops` sorted by position.
+    fn color(&self, stops: &[(f32, [f32; 4])], to: Point, x: u32, y: u32) -> [f32; 4] {
+        let t = self.position(to, [x as f32 + 0.5, y as f32 + 0.5]);
+        match self.banding {
+            Banding::Smooth => ramp(stops, t),
+            Banding::Bands => {
+                let band = ((t * self.bands as f32) as u32).min(self.bands - 1);
+                ramp(stops, band as f32 / (self.bands - 1) as f32)
+            }
+            Banding::Dither => match segment(stops, t) {
+                Segment::Flat(color) => color,
+                Segment::Between(a, b, f) => {
+                    if f > dither_threshold(x, y) {
+                        b
+                    } else {
+                        a
+                    }
+                }
+            },
+        }
+    }
+}
+
+const MIN_BANDS: u32 = 2;
+const MAX_BANDS: u32 = 256;
+
+/// Thresholds of the 4×4 ordered dither, in sixteenths.
+const BAYER: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// The fraction of the way between two stops above which pixel (`x`, `y`)
+/// takes the second stop's color.
+fn dither_threshold(x: u32, y: u32) -> f32 {
+    (BAYER[y as usize % 4][x as usize % 4] as f32 + 0.5) / 16.0
+}
+
+/// Mirrors `GradientUniforms` in the gradient shader.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GradientUniforms {
+    kind: u32,
+    banding: u32,
+    bands: u32,
+    _padding: u32,
+    start: [f32; 2],
+    end: [f32; 2],
+}
+
+/// Mirrors `Stop` in the gradient shader.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GradientStop {
+    color: [f32; 4],
+    position: f32,
+    _padding: [f32; 3],
+}
+
+/// Fills the canvas with a multi-stop gradient, smooth, banded or dithered.
+pub struct GradientProcessor;
+
+impl NodeProcessor for GradientProcessor {
+    fn node_type(&self) -> &'static str {
+        "gradientNode"
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[
+            ParamDef {
+                name: "gradient",
+                kind: ParamKind::Choice {
+                    options: GradientKind::NAMES,
+                    default: "linear",
+                },
+            },
+            ParamDef {
+                name: "from",
+                kind: ParamKind::Point {
+                    default: Some([0, 0]),
+                },
+            },
+            ParamDef {
+                name: "to",
+                kind: ParamKind::Point { default: None },
+            },
+            ParamDef {
+                name: "stops",
+                kind: ParamKind::Gradient,
+            },
+            ParamDef {
+                name: "banding",
+                kind: ParamKind::Choice {
+                    options: Banding::NAMES,
+                    default: "smooth",
+                },
+            },
+            ParamDef {
+                name: "bands",
+                kind: ParamKind::Int {
+                    min: MIN_BANDS,
+                    max: MAX_BANDS,
+                    default: 4,
+                },
+            },
+        ]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let NodeKind::Gradient(params) = &node.kind else {
+            return Err(ctx.error("not a gradient node"));
+        };
+        params.check().map_err(|e| ctx.error(e))?;
+
+        let uniforms = GradientUniforms {
+            kind: params.gradient as u32,
+            banding: params.banding as u32,
+            bands: params.bands,
+            _padding: 0,
+            start: center(params.from),
+            end: center(params.end(ctx.size)),
+        };
+        let stops: Vec<GradientStop> = params
+            .stops
+            .to_rgba(ctx.color_space())
+            .into_iter()
+            .map(|(position, color)| GradientStop {
+                color,
+                position,
+                _padding: [0.0; 3],
+            })
+            .collect();
+
+        let device = ctx.device();
+        let pipeline = ctx
+            .render_context()
+            .pipelines
+            .compute_pipeline(device, &shaders::GRADIENT, "cs_main", ctx.format())
+            .map_err(|e| ctx.error(e))?;
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Gradient Buffer"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let stop_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Gradient Stop Buffer"),
+            contents: bytemuck::cast_slice(&stops),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let texture = ctx.dispatch(
+            &format!("Gradient {}", node.label),
+            &pipeline,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: stop_buffer.as_entire_binding(),
+                },
+            ],
+        )?;
+        Ok(single_output(texture))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Gradient(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a gradient node"));
+        };
+        params.check().map_err(|e| ctx.error(e))?;
+
+        let stops = params.stops.to_rgba(ctx.color_space());
+        let size = ctx.size;
+        let to = params.end(size);
+        let image = CpuImage::from_fn(size, ctx.color_space(), |x, y| {
+            params.color(&stops, to, x, y)
+        });
+        Ok(single_output(Arc::new(image)))
+    }
+}
+
+/// Where a position falls on a ramp.
+enum Segment {
+    /// Before the first stop or after the last.
+    Flat([f32; 4]),
+    /// Between two stops, with how far along from the first to the second.
+    Between([f32; 4], [f32; 4], f32),
+}
+
+fn segment(stops: &[(f32, [f32; 4])], t: f32) -> Segment {
+    match stops.iter().position(|&(position, _)| position > t) {
+        Some(0) => Segment::Flat(stops[0].1),
+        Some(i) => {
+            let ((a, color_a), (b, color_b)) = (stops[i - 1], stops[i]);
+            Segment::Between(color_a, color_b, (t - a) / (b - a))
+        }
+        None => Segment::Flat(stops[stops.len() - 1].1),
+    }
+}
+
+/// The color of a smooth ramp at `t`.
+fn ramp(stops: &[(f32, [f32; 4])], t: f32) -> [f32; 4] {
+    match segment(stops, t) {
+        Segment::Flat(color) => color,
+        Segment::Between(a, b, f) => cpu::mix(a, b, f),
+    }
+}
+
+/// The center of the pixel at `point`.
+fn center(point: Point) -> [f32; 2] {
+    [point.x as f32 + 0.5, point.y as f32 + 0.5]
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+/// Positive where `b` is clockwise of `a` on screen, with y pointing down.
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_space::ColorSpace;
+    use serde_json::json;
+
+    const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+    fn params(settings: serde_json::Value) -> GradientParams {
+        serde_json::from_value(settings).unwrap()
+    }
+
+    /// A stop of gray `value` at `position`.
+    fn stop(position: f32, value: u8) -> serde_json::Value {
+        json!({ "position": position, "color": { "r": value, "g": value, "b": value, "a": 1 } })
+    }
+
+    /// The gradient rendered on a canvas of `size`, row by row.
+    fn render(params: &GradientParams, size: Size) -> Vec<[f32; 4]> {
+        params.check().unwrap();
+        let stops = params.stops.to_rgba(ColorSpace::Gamma);
+        let to = params.end(size);
+        (0..size.height)
+            .flat_map(|y| (0..size.width).map(move |x| (x, y)))
+            .map(|(x, y)| params.color(&stops, to, x, y))
+            .collect()
+    }
+
+    /// The red channel of every pixel.
+    fn reds(pixels: &[[f32; 4]]) -> Vec<f32> {
+        pixels.iter().map(|pixel| pixel[0]).collect()
+    }
+
+    #[test]
+    fn single_stop_fills_the_canvas() {
+        let gradient = params(json!({ "stops": [stop(0.3, 255)] }));
+        assert!(render(&gradient, Size::new(8, 4))
+            .iter()
+            .all(|&c| c == WHITE));
+    }
+
+    #[test]
+    fn stops_at_the_same_position_make_a_hard_edge() {
+        let gradient = params(json!({
+            "to": { "x": 8, "y": 0 },
+            "stops": [stop(0.0, 0), stop(0.5, 0), stop(0.5, 255), stop(1.0, 255)],
+        }));
+        let row = reds(&render(&gradient, Size::new(9, 1)));
+        assert_eq!(row, [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn stops_may_come_in_any_order() {
+        let sorted = params(json!({ "stops": [stop(0.0, 0), stop(0.5, 64), stop(1.0, 255)] }));
+        let unsorted = params(json!({ "stops": [stop(1.0, 255), stop(0.0, 0), stop(0.5, 64)] }));
+        let size = Size::new(16, 2);
+        assert_eq!(render(&unsorted, size), render(&sorted, size));
+    }
+
+    #[test]
+    fn end_defaults_to_the_top_right_pixel() {
+        let gradient = params(json!({}));
+        assert_eq!(gradient.end(Size::new(10, 4)), Point { x: 9, y: 0 });
+        let pixels = render(&gradient, Size::new(10, 4));
+        assert_eq!(pixels[0], BLACK);
+        assert_eq!(pixels[9], WHITE);
+        assert_eq!(pixels[3 * 10 + 9], WHITE);
+    }
+
+    #[test]
+    fn bands_split_the_ramp_evenly() {
+        let gradient = params(json!({
+            "to": { "x": 7, "y": 0 },
+            "banding": "bands",
+            "bands": 4,
+        }));
+        let row = reds(&render(&gradient, Size::new(8, 1)));
+        let third = 1.0 / 3.0;
+        let expected = [0.0, 0.0, third, third, 2.0 * third, 2.0 * third, 1.0, 1.0];
+        for (value, expected) in row.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6, "{:?}", row);
+        }
+    }
+
+    #[test]
+    fn fewer_than_two_bands_are_rejected() {
+        let gradient = params(json!({ "banding": "bands", "bands": 1 }));
+        assert!(gradient.check().is_err());
+        assert!(params(json!({ "stops": [] })).check().is_err());
+    }
+
+    #[test]
+    fn dither_thresholds_cover_every_sixteenth_once() {
+        for level in 0..=16 {
+            let fraction = level as f32 / 16.0;
+            let above = (0..4)
+                .flat_map(|y| (0..4).map(move |x| (x, y)))
+                .filter(|&(x, y)| fraction > dither_threshold(x, y))
+                .count();
+            assert_eq!(above, level);
+        }
+        assert_eq!(dither_threshold(1, 2), dither_threshold(5, 6));
+    }
+
+    #[test]
+    fn dither_only_uses_stop_colors() {
+        let gradient = params(json!({
+            "stops": [stop(0.0, 0), stop(0.5, 128), stop(1.0, 255)],
+            "banding": "dither",
+        }));
+        let palette = gradient.stops.to_rgba(ColorSpace::Gamma);
+        let pixels = render(&gradient, Size::new(32, 8));
+        assert!(pixels
+            .iter()
+            .all(|pixel| palette.iter().any(|(_, color)| color == pixel)));
+        assert_eq!(pixels[0], BLACK);
+        assert_eq!(pixels[31], WHITE);
+    }
+}
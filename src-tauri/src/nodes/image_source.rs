@@ -0,0 +1,302 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, External, NodeContext, NodeProcessor, Outputs, ParamDef,
+    ParamKind,
+};
+use crate::backend::cpu::CpuImage;
+use crate::color_space::ColorSpace;
+use crate::error::EngineError;
+use crate::graph::{Graph, Node, NodeKind, Size};
+use crate::samplers::SamplerSettings;
+use crate::shaders;
+use image::{ImageReader, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use wgpu::util::DeviceExt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ImageSourceParams {
+    /// The image file, absolute or relative to the working directory.
+    #[serde(default)]
+    pub path: String,
+}
+
+/// The formats the node is meant to load, by file extension.
+const EXTENSIONS: &[&str] = &["png", "gif", "bmp", "jpg", "jpeg", "tga"];
+
+#[derive(Clone)]
+struct Decoded {
+    modified: SystemTime,
+    pixels: Arc<RgbaImage>,
+}
+
+/// Loads an image file and outputs it at its native size.
+///
+/// Decoded pixels are kept by path along with the file's modification time,
+/// so an unchanged file is decoded once however often the graph renders, and
+/// a changed one is picked up on the next render. Only the latest version of
+/// each file is kept, and only while a node of the synced graph reads it.
+#[derive(Default)]
+pub struct ImageSourceProcessor {
+    decoded: Mutex<HashMap<PathBuf, Decoded>>,
+}
+
+impl ImageSourceProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The node's file and its modification time.
+    fn file<'n>(&self, node: &'n Node) -> Result<(&'n Path, SystemTime), EngineError> {
+        let NodeKind::ImageSource(params) = &node.kind else {
+            return Err(EngineError::node(&node.id, "not an image node"));
+        };
+        if params.path.is_empty() {
+            return Err(EngineError::node(&node.id, "No image file chosen"));
+        }
+        let path = Path::new(&params.path);
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| unreadable(node, path, e))?;
+        Ok((path, modified))
+    }
+
+    /// The paths held in the cache, for tests.
+    #[cfg(test)]
+    fn cached_paths(&self) -> Vec<PathBuf> {
+        self.decoded.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// The decoded pixels of the node's file, decoding it unless the cache
+    /// holds the same version.
+    fn load(&self, node: &Node) -> Result<Arc<RgbaImage>, EngineError> {
+        let (path, modified) = self.file(node)?;
+        if let Some(cached) = self.cached(path, modified)? {
+            return Ok(cached);
+        }
+
+        let pixels = ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| unreadable(node, path, e))?
+            .decode()
+            .map_err(|e| unreadable(node, path, e))?
+            .to_rgba8();
+        let pixels = Arc::new(pixels);
+        self.decoded.lock()?.insert(
+            path.to_path_buf(),
+            Decoded {
+                modified,
+                pixels: pixels.clone(),
+            },
+        );
+        Ok(pixels)
+    }
+
+    fn cached(
+        &self,
+        path: &Path,
+        modified: SystemTime,
+    ) -> Result<Option<Arc<RgbaImage>>, EngineError> {
+        Ok(self
+            .decoded
+            .lock()?
+            .get(path)
+            .filter(|decoded| decoded.modified == modified)
+            .map(|decoded| decoded.pixels.clone()))
+    }
+}
+
+fn unreadable(node: &Node, path: &Path, error: impl std::fmt::Display) -> EngineError {
+    EngineError::node(
+        &node.id,
+        format!("Cannot read image {}: {}", path.display(), error),
+    )
+}
+
+/// A pixel of the sRGB-encoded file in the working space. Alpha is stored
+/// as is.
+fn texel(pixel: [u8; 4], space: ColorSpace) -> [f32; 4] {
+    let [r, g, b, a] = pixel;
+    let channel = |c: u8| space.from_srgb(c as f32 / 255.0);
+    [channel(r), channel(g), channel(b), a as f32 / 255.0]
+}
+
+/// The pixels in the layout of a texture of `space`'s format.
+fn texture_data(pixels: &RgbaImage, space: ColorSpace) -> Vec<u8> {
+    match space {
+        ColorSpace::Gamma => pixels.as_raw().clone(),
+        ColorSpace::Linear => pixels
+            .pixels()
+            .flat_map(|pixel| texel(pixel.0, space))
+            .flat_map(|c| half::f16::from_f32(c).to_le_bytes())
+            .collect(),
+    }
+}
+
+impl NodeProcessor for ImageSourceProcessor {
+    fn node_type(&self) -> &'static str {
+        "imageNode"
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[ParamDef {
+            name: "path",
+            kind: ParamKind::Path {
+                extensions: EXTENSIONS,
+            },
+        }]
+    }
+
+    /// The file's native size, read from its header unless it is already
+    /// decoded, and its modification time.
+    fn external(&self, node: &Node) -> Result<Option<External>, EngineError> {
+        let (path, modified) = self.file(node)?;
+        let (width, height) = match self.cached(path, modified)? {
+            Some(pixels) => pixels.dimensions(),
+            None => ImageReader::open(path)
+                .and_then(|reader| reader.with_guessed_format())
+                .map_err(|e| unreadable(node, path, e))?
+                .into_dimensions()
+                .map_err(|e| unreadable(node, path, e))?,
+        };
+
+        let mut hasher = DefaultHasher::new();
+        modified.hash(&mut hasher);
+        Ok(Some(External {
+            size: Size::new(width, height),
+            version: hasher.finish(),
+        }))
+    }
+
+    /// Drops the pixels of files no node of `graph` reads any more.
+    fn graph_synced(&self, graph: &Graph) {
+        let read: HashSet<&Path> = graph
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.kind {
+                NodeKind::ImageSource(params) => Some(Path::new(&params.path)),
+                _ => None,
+            })
+            .collect();
+        if let Ok(mut decoded) = self.decoded.lock() {
+            decoded.retain(|path, _| read.contains(path.as_path()));
+        }
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let pixels = self.load(node)?;
+        let texture = ctx.create_texture(&format!("Image {}", node.label))?;
+
+        // Uploaded in the working format and drawn into the output, which
+        // also scales it if the node's size is overridden.
+        let device = ctx.device();
+        let (width, height) = pixels.dimensions();
+        let upload = device.create_texture_with_data(
+            &ctx.render_context().queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Image Upload"),
+                size: Size::new(width, height).extent(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: ctx.format(),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &texture_data(&pixels, ctx.color_space()),
+        );
+        let upload_view = upload.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let copy = ctx
+            .render_context()
+            .pipelines
+            .render_pipeline(device, &shaders::OUTPUT, "fs_copy", ctx.format())
+            .map_err(|e| ctx.error(e))?;
+        let sampler = ctx
+            .render_context()
+            .samplers
+            .get(device, SamplerSettings::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Image Bind Group"),
+            layout: &copy.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&upload_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        ctx.draw_fullscreen("Image Pass", &copy.pipeline, &bind_group, &texture);
+        Ok(single_output(texture))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let pixels = self.load(ctx.node())?;
+        let (width, height) = pixels.dimensions();
+        let space = ctx.color_space();
+        let image = CpuImage::from_fn(Size::new(width, height), space, |x, y| {
+            texel(pixels.get_pixel(x, y).0, space)
+        });
+
+        if image.size == ctx.size {
+            Ok(single_output(Arc::new(image)))
+        } else {
+            Ok(single_output(ctx.shade(|uv| image.sample_nearest(uv))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::graph_from;
+    use serde_json::json;
+
+    /// A graph of one image node per path.
+    fn graph(paths: &[&Path]) -> Graph {
+        let ids: Vec<_> = (0..paths.len()).map(|i| format!("image{}", i)).collect();
+        let nodes: Vec<_> = ids
+            .iter()
+            .zip(paths)
+            .map(|(id, path)| (id.as_str(), "imageNode", json!({ "path": path })))
+            .collect();
+        graph_from(&nodes, &[])
+    }
+
+    #[test]
+    fn files_no_node_reads_are_dropped_on_sync() {
+        let dir = std::env::temp_dir().join(format!("pixel-forge-images-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.png"), dir.join("b.png"));
+        for path in [&a, &b] {
+            RgbaImage::new(2, 2).save(path).unwrap();
+        }
+
+        let processor = ImageSourceProcessor::new();
+        let both = graph(&[&a, &b]);
+        processor.graph_synced(&both);
+        for node in &both.nodes {
+            processor.load(node).unwrap();
+        }
+        assert_eq!(processor.cached_paths().len(), 2);
+
+        processor.graph_synced(&graph(&[&b]));
+        assert_eq!(processor.cached_paths(), std::slice::from_ref(&b));
+
+        processor.graph_synced(&graph(&[]));
+        assert!(processor.cached_paths().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
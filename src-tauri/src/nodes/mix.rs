@@ -0,0 +1,153 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef,
+    ParamKind, PortDef,
+};
+use crate::backend::cpu;
+use crate::error::EngineError;
+use crate::graph::NodeKind;
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MixParams {
+    #[serde(default = "MixParams::default_factor")]
+    pub factor: f32,
+}
+
+impl MixParams {
+    fn default_factor() -> f32 {
+        0.5
+    }
+}
+
+/// Mirrors `MixUniforms` in the mix shader.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MixUniforms {
+    factor: f32,
+    _padding: [f32; 3],
+    sampling_a: [u32; 4],
+    sampling_b: [u32; 4],
+}
+
+/// Linearly blends input `a` towards input `b` by `factor`.
+pub struct MixProcessor;
+
+impl NodeProcessor for MixProcessor {
+    fn node_type(&self) -> &'static str {
+        "mixNode"
+    }
+
+    fn inputs(&self) -> &'static [PortDef] {
+        &[
+            PortDef {
+                handle: "a",
+                label: "A",
+            },
+            PortDef {
+                handle: "b",
+                label: "B",
+            },
+        ]
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[ParamDef {
+            name: "factor",
+            kind: ParamKind::Float {
+                min: 0.0,
+                max: 1.0,
+                default: 0.5,
+            },
+        }]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let NodeKind::Mix(params) = &node.kind else {
+            return Err(ctx.error("not a mix node"));
+        };
+
+        let texture_a = ctx.input("a")?;
+        let texture_b = ctx.input("b")?;
+        let view_a = texture_a.create_view(&wgpu::TextureViewDescriptor::default());
+        let view_b = texture_b.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler_a = ctx.sampler("a");
+        let sampler_b = ctx.sampler("b");
+
+        let texture = ctx.create_texture(&format!("Mix {}", node.label))?;
+
+        let device = ctx.device();
+        let mix = ctx
+            .render_context()
+            .pipelines
+            .render_pipeline(device, &shaders::MIX, "fs_main", ctx.format())
+            .map_err(|e| ctx.error(e))?;
+
+        let uniform_data = MixUniforms {
+            factor: params.factor,
+            _padding: [0.0; 3],
+            sampling_a: ctx.sampling("a"),
+            sampling_b: ctx.sampling("b"),
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mix Buffer"),
+            contents: bytemuck::bytes_of(&uniform_data),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mix Bind Group"),
+            layout: &mix.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view_a),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view_b),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler_a),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&sampler_b),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &uniform_buffer,
+                        offset: 0,
+                        size: None,
+                    }),
+                },
+            ],
+        });
+
+        ctx.draw_fullscreen("Mix Pass", &mix.pipeline, &bind_group, &texture);
+
+        Ok(single_output(texture))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Mix(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a mix node"));
+        };
+        let a = ctx.input("a")?;
+        let b = ctx.input("b")?;
+        let image = ctx.shade(|uv| {
+            cpu::mix(
+                ctx.sample(&a, "a", uv),
+                ctx.sample(&b, "b", uv),
+                params.factor,
+            )
+        });
+        Ok(single_output(image))
+    }
+}
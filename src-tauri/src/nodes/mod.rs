@@ -0,0 +1,469 @@
+//! Node types and the registry the evaluator looks them up in.
+//!
+//! Each node type lives in its own module: its parameter struct, which is
+//! wired into [`NodeKind`](crate::graph::NodeKind), and a [`NodeProcessor`]
+//! that describes the node, records its GPU work and renders the same image
+//! on the CPU.
+
+pub mod color;
+pub mod dilate;
+pub mod gradient;
+pub mod image_source;
+pub mod mix;
+pub mod noise;
+pub mod output;
+pub mod pattern;
+pub mod shape;
+pub mod split;
+
+use crate::backend::cpu::{pixel_uv, CpuEvaluator, CpuImage};
+use crate::color_space::ColorSpace;
+use crate::error::EngineError;
+use crate::evaluator::Evaluator;
+use crate::graph::{Graph, Node, Size, DEFAULT_OUTPUT};
+use crate::pipelines::ComputePipeline;
+use crate::scheduler::Step;
+use crate::shaders::WORKGROUP_SIZE;
+use crate::state::RenderContext;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Output textures of one node, keyed by output handle.
+pub type Outputs = HashMap<String, Arc<wgpu::Texture>>;
+
+/// Output images of one node on the CPU backend, keyed by output handle.
+pub type CpuOutputs = HashMap<String, Arc<CpuImage>>;
+
+/// An input or output handle of a node type.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct PortDef {
+    pub handle: &'static str,
+    pub label: &'static str,
+}
+
+/// The kind of value a parameter holds, with its editor hints.
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ParamKind {
+    Float {
+        min: f32,
+        max: f32,
+        default: f32,
+    },
+    Int {
+        min: u32,
+        max: u32,
+        default: u32,
+    },
+    Color,
+    /// A list of color stops.
+    Gradient,
+    Bool {
+        default: bool,
+    },
+    /// A pixel position, as `{ x, y }`. With no default the node works one
+    /// out from the canvas.
+    Point {
+        default: Option<[i32; 2]>,
+    },
+    /// One of a fixed set of names.
+    Choice {
+        options: &'static [&'static str],
+        default: &'static str,
+    },
+    /// A file on disk, picked from files with one of `extensions`.
+    Path {
+        extensions: &'static [&'static str],
+    },
+}
+
+/// One entry of a node type's parameter schema.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct ParamDef {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub kind: ParamKind,
+}
+
+/// Describes a node type for the frontend.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeTypeInfo {
+    pub node_type: &'static str,
+    pub inputs: &'static [PortDef],
+    pub outputs: &'static [PortDef],
+    pub params: &'static [ParamDef],
+}
+
+/// What a node reads from outside the graph, e.g. a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct External {
+    /// The node's natural output size, used unless the node overrides it.
+    pub size: Size,
+    /// Changes whenever the source changes, so the node's cached outputs
+    /// are not reused after an edit to the file.
+    pub version: u64,
+}
+
+/// Implemented once per node type.
+pub trait NodeProcessor: Send + Sync {
+    /// The React Flow `type` this processor handles.
+    fn node_type(&self) -> &'static str;
+
+    fn inputs(&self) -> &'static [PortDef] {
+        &[]
+    }
+
+    fn outputs(&self) -> &'static [PortDef] {
+        &[PortDef {
+            handle: DEFAULT_OUTPUT,
+            label: "Out",
+        }]
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[]
+    }
+
+    /// Checks what the node reads from outside the graph before it is
+    /// scheduled. Nodes that only depend on their parameters and inputs
+    /// return `None`.
+    fn external(&self, _node: &Node) -> Result<Option<External>, EngineError> {
+        Ok(None)
+    }
+
+    /// Called with every graph the editor syncs, so processors that keep
+    /// state across renders can drop what only removed nodes needed.
+    fn graph_synced(&self, _graph: &Graph) {}
+
+    /// Records the node's GPU work and returns its outputs by handle.
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError>;
+
+    /// Renders the same outputs as [`process`](Self::process) on the CPU.
+    /// This is the reference the shaders are checked against, so it should
+    /// read as a direct statement of what the node computes.
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError>;
+
+    fn info(&self) -> NodeTypeInfo {
+        NodeTypeInfo {
+            node_type: self.node_type(),
+            inputs: self.inputs(),
+            outputs: self.outputs(),
+            params: self.params(),
+        }
+    }
+}
+
+/// What a processor gets to work with while processing one node.
+pub struct NodeContext<'e, 'a> {
+    pub(crate) evaluator: &'e mut Evaluator<'a>,
+    pub step: &'e Step<'e>,
+    /// The output size resolved for this node.
+    pub size: Size,
+}
+
+impl<'e, 'a> NodeContext<'e, 'a> {
+    pub fn node(&self) -> &'e Node {
+        self.step.node
+    }
+
+    pub fn render_context(&self) -> &'a RenderContext {
+        self.evaluator.context
+    }
+
+    pub fn device(&self) -> &'a wgpu::Device {
+        &self.evaluator.context.device
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.evaluator.color_space
+    }
+
+    /// The format of the textures [`create_texture`](Self::create_texture)
+    /// returns, which pipelines drawing into them must target.
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.evaluator.color_space.format()
+    }
+
+    /// An evaluation error for this node.
+    pub fn error(&self, message: impl Into<String>) -> EngineError {
+        EngineError::node(&self.step.node.id, message)
+    }
+
+    /// The texture connected to input `handle`, or black if it is unconnected.
+    pub fn input(&mut self, handle: &str) -> Result<Arc<wgpu::Texture>, EngineError> {
+        self.evaluator
+            .input(self.step, handle, self.size)
+            .map_err(|e| EngineError::node_handle(&self.step.node.id, handle, e))
+    }
+
+    /// The sampler set up for input `handle`, to be passed to the shader's
+    /// `sample_input` together with [`sampling`](Self::sampling).
+    pub fn sampler(&self, handle: &str) -> Arc<wgpu::Sampler> {
+        self.render_context()
+            .samplers
+            .get(self.device(), self.step.node.sampler(handle))
+    }
+
+    /// The `Sampling` uniform for input `handle`.
+    pub fn sampling(&self, handle: &str) -> [u32; 4] {
+        self.step.node.sampler(handle).sampling()
+    }
+
+    /// Allocates an output texture at the node's resolved size.
+    pub fn create_texture(&mut self, label: &str) -> Result<Arc<wgpu::Texture>, EngineError> {
+        self.evaluator
+            .create_texture(label, self.size)
+            .map_err(|e| self.error(e))
+    }
+
+    pub fn clear(&mut self, texture: &wgpu::Texture, color: wgpu::Color) {
+        self.evaluator.clear(texture, color);
+    }
+
+    /// Records a full-screen pass drawing `pipeline` into `target`.
+    pub fn draw_fullscreen(
+        &mut self,
+        label: &str,
+        pipeline: &wgpu::RenderPipeline,
+        bind_group: &wgpu::BindGroup,
+        target: &wgpu::Texture,
+    ) {
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut pass = self
+            .evaluator
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Allocates an output texture and records a compute pass running
+    /// `pipeline` once per pixel of it. The texture is bound at binding 0,
+    /// followed by `bindings`.
+    pub fn dispatch(
+        &mut self,
+        label: &str,
+        pipeline: &ComputePipeline,
+        bindings: &[wgpu::BindGroupEntry],
+    ) -> Result<Arc<wgpu::Texture>, EngineError> {
+        let target = self.create_texture(label)?;
+        self.record_dispatch(label, pipeline, &target, bindings);
+        Ok(target)
+    }
+
+    /// Runs `pipeline` `passes` times, alternating between two output
+    /// textures. Binding 1 of each pass is the result of the previous one,
+    /// or `source` for the first, and `bindings` follow. Returns the result
+    /// of the last pass, or `source` if there are none.
+    pub fn dispatch_ping_pong(
+        &mut self,
+        label: &str,
+        pipeline: &ComputePipeline,
+        source: Arc<wgpu::Texture>,
+        passes: u32,
+        bindings: &[wgpu::BindGroupEntry],
+    ) -> Result<Arc<wgpu::Texture>, EngineError> {
+        if passes == 0 {
+            return Ok(source);
+        }
+
+        let view = source.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut current = self.dispatch(label, pipeline, &after_previous(&view, bindings))?;
+        let mut spare = None;
+        for _ in 1..passes {
+            let target = match spare.take() {
+                Some(texture) => texture,
+                None => self.create_texture(label)?,
+            };
+            let view = current.create_view(&wgpu::TextureViewDescriptor::default());
+            self.record_dispatch(label, pipeline, &target, &after_previous(&view, bindings));
+            spare = Some(std::mem::replace(&mut current, target));
+        }
+        if let Some(texture) = spare {
+            self.evaluator.transient.push(texture);
+        }
+        Ok(current)
+    }
+
+    fn record_dispatch(
+        &mut self,
+        label: &str,
+        pipeline: &ComputePipeline,
+        target: &wgpu::Texture,
+        bindings: &[wgpu::BindGroupEntry],
+    ) {
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(&view),
+        }];
+        entries.extend_from_slice(bindings);
+        let bind_group = self.device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: &pipeline.bind_group_layout,
+            entries: &entries,
+        });
+
+        let mut pass = self
+            .evaluator
+            .encoder
+            .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None,
+            });
+        pass.set_pipeline(&pipeline.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(
+            self.size.width.div_ceil(WORKGROUP_SIZE),
+            self.size.height.div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+}
+
+/// `bindings` preceded by the previous pass of a ping-pong at binding 1.
+fn after_previous<'b>(
+    previous: &'b wgpu::TextureView,
+    bindings: &[wgpu::BindGroupEntry<'b>],
+) -> Vec<wgpu::BindGroupEntry<'b>> {
+    let mut entries = vec![wgpu::BindGroupEntry {
+        binding: 1,
+        resource: wgpu::BindingResource::TextureView(previous),
+    }];
+    entries.extend_from_slice(bindings);
+    entries
+}
+
+/// What a processor gets to work with while rendering one node on the CPU.
+pub struct CpuContext<'e, 'a> {
+    pub(crate) evaluator: &'e mut CpuEvaluator<'a>,
+    pub step: &'e Step<'e>,
+    /// The output size resolved for this node.
+    pub size: Size,
+}
+
+impl CpuContext<'_, '_> {
+    pub fn node(&self) -> &Node {
+        self.step.node
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.evaluator.color_space
+    }
+
+    /// An evaluation error for this node.
+    pub fn error(&self, message: impl Into<String>) -> EngineError {
+        EngineError::node(&self.step.node.id, message)
+    }
+
+    /// The image connected to input `handle`, or black if it is unconnected.
+    pub fn input(&self, handle: &str) -> Result<Arc<CpuImage>, EngineError> {
+        self.evaluator
+            .input(self.step, handle, self.size)
+            .map_err(|e| EngineError::node_handle(&self.step.node.id, handle, e))
+    }
+
+    /// Samples `image`, the image connected to input `handle`, at normalized
+    /// coordinates with the sampler set up for that input.
+    pub fn sample(&self, image: &CpuImage, handle: &str, uv: [f32; 2]) -> [f32; 4] {
+        image.sample(uv, self.step.node.sampler(handle))
+    }
+
+    /// Builds an output image at the node's resolved size by running `shade`
+    /// for the normalized coordinates of each pixel center, the CPU
+    /// counterpart of [`NodeContext::draw_fullscreen`].
+    pub fn shade(&self, mut shade: impl FnMut([f32; 2]) -> [f32; 4]) -> Arc<CpuImage> {
+        let size = self.size;
+        Arc::new(CpuImage::from_fn(size, self.color_space(), |x, y| {
+            shade(pixel_uv(size, x, y))
+        }))
+    }
+}
+
+/// Wraps a single image as the outputs of a node with one output.
+pub fn single_output<T>(image: T) -> HashMap<String, T> {
+    HashMap::from([(DEFAULT_OUTPUT.to_string(), image)])
+}
+
+/// Node processors by node type.
+#[derive(Default)]
+pub struct NodeRegistry {
+    processors: HashMap<&'static str, Box<dyn NodeProcessor>>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with every node type that ships with the app.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(color::ColorProcessor);
+        registry.register(dilate::DilateProcessor);
+        registry.register(gradient::GradientProcessor);
+        registry.register(image_source::ImageSourceProcessor::new());
+        registry.register(mix::MixProcessor);
+        registry.register(noise::NoiseProcessor);
+        registry.register(output::OutputProcessor);
+        registry.register(pattern::PatternProcessor);
+        registry.register(shape::ShapeProcessor);
+        registry.register(split::SplitProcessor);
+        registry
+    }
+
+    pub fn register(&mut self, processor: impl NodeProcessor + 'static) {
+        self.processors
+            .insert(processor.node_type(), Box::new(processor));
+    }
+
+    pub fn get(&self, node_type: &str) -> Option<&dyn NodeProcessor> {
+        self.processors.get(node_type).map(|p| p.as_ref())
+    }
+
+    /// Passes a newly synced graph to every processor.
+    pub fn graph_synced(&self, graph: &Graph) {
+        for processor in self.processors.values() {
+            processor.graph_synced(graph);
+        }
+    }
+
+    /// Descriptions of all registered node types, sorted by type.
+    pub fn infos(&self) -> Vec<NodeTypeInfo> {
+        let mut infos: Vec<_> = self.processors.values().map(|p| p.info()).collect();
+        infos.sort_by_key(|info| info.node_type);
+        infos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::NodeKind;
+
+    #[test]
+    fn every_node_type_has_a_builtin_processor() {
+        let registry = NodeRegistry::builtin();
+        for &type_name in NodeKind::TYPE_NAMES {
+            let processor = registry.get(type_name);
+            assert!(processor.is_some(), "{} is not registered", type_name);
+        }
+        assert_eq!(registry.infos().len(), NodeKind::TYPE_NAMES.len());
+    }
+}
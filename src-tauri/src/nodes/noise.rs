@@ -0,0 +1,559 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef, ParamKind,
+};
+use crate::error::EngineError;
+use crate::graph::NodeKind;
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+/// The basis function a noise node layers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NoiseKind {
+    /// Random values at lattice points, smoothly interpolated. Blocky.
+    Value,
+    /// Random gradients at lattice points.
+    #[default]
+    Perlin,
+    /// Random gradients on a triangular lattice, with fewer axis-aligned
+    /// artifacts than Perlin.
+    Simplex,
+    /// Distance to the nearest of one random point per cell, for cells and
+    /// cracks.
+    Worley,
+}
+
+impl NoiseKind {
+    /// The names the kinds are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["value", "perlin", "simplex", "worley"];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseParams {
+    #[serde(default)]
+    pub noise: NoiseKind,
+    #[serde(default)]
+    pub seed: u32,
+    /// Lattice cells across the canvas in each direction for the first
+    /// octave.
+    #[serde(default = "NoiseParams::default_scale")]
+    pub scale: f32,
+    /// Layers of noise summed into fractal Brownian motion; 1 is the plain
+    /// basis function.
+    #[serde(default = "NoiseParams::default_octaves")]
+    pub octaves: u32,
+    /// How much the frequency grows from one octave to the next.
+    #[serde(default = "NoiseParams::default_lacunarity")]
+    pub lacunarity: f32,
+    /// How much the amplitude shrinks from one octave to the next.
+    #[serde(default = "NoiseParams::default_gain")]
+    pub gain: f32,
+    /// Wraps every octave at the canvas edges, so the output tiles. Octave
+    /// frequencies are rounded to whole cells.
+    #[serde(default)]
+    pub tileable: bool,
+}
+
+impl NoiseParams {
+    fn default_scale() -> f32 {
+        4.0
+    }
+
+    fn default_octaves() -> u32 {
+        1
+    }
+
+    fn default_lacunarity() -> f32 {
+        2.0
+    }
+
+    fn default_gain() -> f32 {
+        0.5
+    }
+
+    /// The frequency and weight of each octave. Weights add up to one, so
+    /// the sum stays in `0.0..=1.0` like a single octave.
+    fn octaves(&self) -> Result<Vec<(f32, f32)>, String> {
+        if !(1..=MAX_OCTAVES).contains(&self.octaves) {
+            return Err(format!("Octaves must be between 1 and {}", MAX_OCTAVES));
+        }
+        if self.scale <= 0.0 || !self.scale.is_finite() {
+            return Err("Scale must be a positive number".to_string());
+        }
+        if self.lacunarity < MIN_LACUNARITY || !self.lacunarity.is_finite() {
+            return Err(format!("Lacunarity must be at least {}", MIN_LACUNARITY));
+        }
+        if self.gain <= 0.0 || !self.gain.is_finite() {
+            return Err("Gain must be a positive number".to_string());
+        }
+
+        let mut octaves = Vec::new();
+        let (mut frequency, mut amplitude) = (self.scale, 1.0);
+        for _ in 0..self.octaves {
+            let cells = match self.tileable {
+                true => frequency.round().max(1.0),
+                false => frequency,
+            };
+            octaves.push((cells, amplitude));
+            (frequency, amplitude) = (frequency * self.lacunarity, amplitude * self.gain);
+        }
+        let total: f32 = octaves.iter().map(|(_, amplitude)| amplitude).sum();
+        Ok(octaves
+            .into_iter()
+            .map(|(frequency, amplitude)| (frequency, amplitude / total))
+            .collect())
+    }
+}
+
+/// Most octaves a node can layer, the length of the arrays in
+/// `NoiseUniforms`.
+const MAX_OCTAVES: u32 = 8;
+/// Octaves never get coarser than the first.
+const MIN_LACUNARITY: f32 = 1.0;
+/// The smallest gain the slider offers; any positive gain is accepted.
+const MIN_GAIN: f32 = 0.01;
+
+/// Mirrors `NoiseUniforms` in the noise shader.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct NoiseUniforms {
+    kind: u32,
+    seed: u32,
+    octaves: u32,
+    tileable: u32,
+    frequencies: [f32; MAX_OCTAVES as usize],
+    weights: [f32; MAX_OCTAVES as usize],
+}
+
+/// Generates grayscale noise over the canvas from a seed, the same on every
+/// run and on either backend. Values are written as they are, like alpha,
+/// so noise can drive masks and factors in either working space.
+pub struct NoiseProcessor;
+
+impl NodeProcessor for NoiseProcessor {
+    fn node_type(&self) -> &'static str {
+        "noiseNode"
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[
+            ParamDef {
+                name: "noise",
+                kind: ParamKind::Choice {
+                    options: NoiseKind::NAMES,
+                    default: "perlin",
+                },
+            },
+            ParamDef {
+                name: "seed",
+                kind: ParamKind::Int {
+                    min: 0,
+                    max: u32::MAX,
+                    default: 0,
+                },
+            },
+            ParamDef {
+                name: "scale",
+                kind: ParamKind::Float {
+                    min: 1.0,
+                    max: 64.0,
+                    default: 4.0,
+                },
+            },
+            ParamDef {
+                name: "octaves",
+                kind: ParamKind::Int {
+                    min: 1,
+                    max: MAX_OCTAVES,
+                    default: 1,
+                },
+            },
+            ParamDef {
+                name: "lacunarity",
+                kind: ParamKind::Float {
+                    min: MIN_LACUNARITY,
+                    max: 4.0,
+                    default: 2.0,
+                },
+            },
+            ParamDef {
+                name: "gain",
+                kind: ParamKind::Float {
+                    min: MIN_GAIN,
+                    max: 1.0,
+                    default: 0.5,
+                },
+            },
+            ParamDef {
+                name: "tileable",
+                kind: ParamKind::Bool { default: false },
+            },
+        ]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let NodeKind::Noise(params) = &node.kind else {
+            return Err(ctx.error("not a noise node"));
+        };
+        let octaves = params.octaves().map_err(|e| ctx.error(e))?;
+
+        let mut uniforms = NoiseUniforms {
+            kind: params.noise as u32,
+            seed: params.seed,
+            octaves: octaves.len() as u32,
+            tileable: params.tileable.into(),
+            frequencies: [0.0; MAX_OCTAVES as usize],
+            weights: [0.0; MAX_OCTAVES as usize],
+        };
+        for (i, (frequency, weight)) in octaves.into_iter().enumerate() {
+            uniforms.frequencies[i] = frequency;
+            uniforms.weights[i] = weight;
+        }
+
+        let device = ctx.device();
+        let pipeline = ctx
+            .render_context()
+            .pipelines
+            .compute_pipeline(device, &shaders::NOISE, "cs_main", ctx.format())
+            .map_err(|e| ctx.error(e))?;
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Noise Buffer"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let texture = ctx.dispatch(
+            &format!("Noise {}", node.label),
+            &pipeline,
+            &[wgpu::BindGroupEntry {
+                binding: 1,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        )?;
+        Ok(single_output(texture))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Noise(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a noise node"));
+        };
+        let octaves = params.octaves().map_err(|e| ctx.error(e))?;
+
+        let image = ctx.shade(|[u, v]| {
+            let value = octaves
+                .iter()
+                .enumerate()
+                .map(|(i, &(frequency, weight))| {
+                    let lattice = Lattice {
+                        seed: hash(params.seed.wrapping_add((i as u32).wrapping_mul(GOLDEN))),
+                        period: params.tileable.then_some(frequency as i32),
+                    };
+                    let p = [u * frequency, v * frequency];
+                    weight * lattice.noise(params.noise, p)
+                })
+                .sum::<f32>();
+            [value, value, value, 1.0]
+        });
+        Ok(single_output(image))
+    }
+}
+
+// The CPU reference below follows `NOISE_SHADER` step by step. Lattice
+// points are hashed with integer arithmetic only, so both backends pick the
+// same random values bit for bit.
+
+/// Spreads octave seeds apart: 2^32 divided by the golden ratio.
+const GOLDEN: u32 = 0x9e37_79b9;
+
+/// Skew and unskew factors between the square and the triangular lattice of
+/// simplex noise: (√3 - 1) / 2 and (3 - √3) / 6.
+const SIMPLEX_SKEW: f32 = 0.366_025_4;
+const SIMPLEX_UNSKEW: f32 = 0.211_324_87;
+/// The same factors in four dimensions: (√5 - 1) / 4 and (5 - √5) / 20.
+const SIMPLEX_SKEW_4D: f32 = 0.309_017;
+const SIMPLEX_UNSKEW_4D: f32 = 0.138_196_6;
+
+/// The PCG hash (Jarzynski and Olano, 2020).
+fn hash(x: u32) -> u32 {
+    let state = x.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277_803_737);
+    (word >> 22) ^ word
+}
+
+/// The top 24 bits of `h` in `0.0..1.0`, which `f32` holds exactly.
+fn unit(h: u32) -> f32 {
+    (h >> 8) as f32 / 16_777_216.0
+}
+
+/// One of eight gradient directions, picked by the low bits of `h`.
+fn gradient(h: u32) -> [f32; 2] {
+    match h & 7 {
+        0 => [1.0, 1.0],
+        1 => [-1.0, 1.0],
+        2 => [1.0, -1.0],
+        3 => [-1.0, -1.0],
+        4 => [1.0, 0.0],
+        5 => [-1.0, 0.0],
+        6 => [0.0, 1.0],
+        _ => [0.0, -1.0],
+    }
+}
+
+/// Perlin's quintic fade curve.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn dot_4d(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3]
+}
+
+/// One of the 32 edge midpoints of the 4D hypercube, picked by the low bits
+/// of `h`: bits 3 and 4 choose the zero axis, bits 0 to 2 the signs of the
+/// other three.
+fn gradient_4d(h: u32) -> [f32; 4] {
+    let zero = (h >> 3 & 3) as usize;
+    let mut g = [0.0; 4];
+    let mut bit = 0;
+    for (axis, g) in g.iter_mut().enumerate() {
+        if axis != zero {
+            *g = if h >> bit & 1 == 0 { 1.0 } else { -1.0 };
+            bit += 1;
+        }
+    }
+    g
+}
+
+/// The random lattice of one octave.
+struct Lattice {
+    seed: u32,
+    /// Cells after which the lattice repeats, when tiling.
+    period: Option<i32>,
+}
+
+impl Lattice {
+    /// The random value of lattice point `cell`.
+    fn hash(&self, [x, y]: [i32; 2]) -> u32 {
+        let (x, y) = match self.period {
+            Some(period) => (x.rem_euclid(period), y.rem_euclid(period)),
+            None => (x, y),
+        };
+        hash(x as u32 ^ hash(y as u32 ^ self.seed))
+    }
+
+    /// The noise at lattice coordinates `p`, in `0.0..=1.0`.
+    fn noise(&self, kind: NoiseKind, p: [f32; 2]) -> f32 {
+        match kind {
+            NoiseKind::Value => self.value(p),
+            NoiseKind::Perlin => (0.5 + 0.5 * self.perlin(p)).clamp(0.0, 1.0),
+            NoiseKind::Simplex => (0.5 + 0.5 * self.simplex(p)).clamp(0.0, 1.0),
+            NoiseKind::Worley => self.worley(p).clamp(0.0, 1.0),
+        }
+    }
+
+    fn value(&self, [x, y]: [f32; 2]) -> f32 {
+        let cell = [x.floor() as i32, y.floor() as i32];
+        let (u, v) = (fade(x - x.floor()), fade(y - y.floor()));
+        let corner = |dx, dy| unit(self.hash([cell[0] + dx, cell[1] + dy]));
+        lerp(
+            lerp(corner(0, 0), corner(1, 0), u),
+            lerp(corner(0, 1), corner(1, 1), u),
+            v,
+        )
+    }
+
+    fn perlin(&self, [x, y]: [f32; 2]) -> f32 {
+        let cell = [x.floor() as i32, y.floor() as i32];
+        let f = [x - x.floor(), y - y.floor()];
+        let corner = |dx: i32, dy: i32| {
+            let g = gradient(self.hash([cell[0] + dx, cell[1] + dy]));
+            dot(g, [f[0] - dx as f32, f[1] - dy as f32])
+        };
+        let (u, v) = (fade(f[0]), fade(f[1]));
+        lerp(
+            lerp(corner(0, 0), corner(1, 0), u),
+            lerp(corner(0, 1), corner(1, 1), u),
+            v,
+        )
+    }
+
+    /// Simplex noise in about `-1.0..=1.0`.
+    ///
+    /// The skewed lattice only repeats along its own axes, never along both
+    /// of the canvas's, so a tiling lattice is sampled on a torus in 4D
+    /// instead: each canvas axis goes once around a circle whose
+    /// circumference is the period, which keeps cells the same size.
+    fn simplex(&self, [x, y]: [f32; 2]) -> f32 {
+        if let Some(period) = self.period {
+            let radius = period as f32 / std::f32::consts::TAU;
+            let (a, b) = (
+                x / period as f32 * std::f32::consts::TAU,
+                y / period as f32 * std::f32::consts::TAU,
+            );
+            return self.simplex_4d([
+                radius * a.cos(),
+                radius * a.sin(),
+                radius * b.cos(),
+                radius * b.sin(),
+            ]);
+        }
+
+        let s = (x + y) * SIMPLEX_SKEW;
+        let cell = [(x + s).floor(), (y + s).floor()];
+        let t = (cell[0] + cell[1]) * SIMPLEX_UNSKEW;
+        let d0 = [x - (cell[0] - t), y - (cell[1] - t)];
+        let step: [i32; 2] = if d0[0] > d0[1] { [1, 0] } else { [0, 1] };
+        let d1 = [
+            d0[0] - step[0] as f32 + SIMPLEX_UNSKEW,
+            d0[1] - step[1] as f32 + SIMPLEX_UNSKEW,
+        ];
+        let d2 = [
+            d0[0] - 1.0 + 2.0 * SIMPLEX_UNSKEW,
+            d0[1] - 1.0 + 2.0 * SIMPLEX_UNSKEW,
+        ];
+
+        let cell = [cell[0] as i32, cell[1] as i32];
+        let corner = |d: [f32; 2], dx: i32, dy: i32| {
+            let t = 0.5 - dot(d, d);
+            if t <= 0.0 {
+                return 0.0;
+            }
+            let g = gradient(self.hash([cell[0] + dx, cell[1] + dy]));
+            t * t * t * t * dot(g, d)
+        };
+        70.0 * (corner(d0, 0, 0) + corner(d1, step[0], step[1]) + corner(d2, 1, 1))
+    }
+
+    /// Gustavson's 4D simplex noise in about `-1.0..=1.0`. Lattice points
+    /// are never wrapped; the torus in `simplex` is what repeats.
+    fn simplex_4d(&self, p: [f32; 4]) -> f32 {
+        let s = p.iter().sum::<f32>() * SIMPLEX_SKEW_4D;
+        let cell = p.map(|x| (x + s).floor());
+        let t = cell.iter().sum::<f32>() * SIMPLEX_UNSKEW_4D;
+        let d0: [f32; 4] = std::array::from_fn(|i| p[i] - (cell[i] - t));
+
+        // Each axis's rank among the offsets decides at which of the four
+        // steps through the simplex it is taken.
+        let mut rank = [0; 4];
+        for i in 0..4 {
+            for j in i + 1..4 {
+                if d0[i] > d0[j] {
+                    rank[i] += 1;
+                } else {
+                    rank[j] += 1;
+                }
+            }
+        }
+
+        let cell = cell.map(|x| x as i32);
+        let corner = |n: i32| {
+            let offset = rank.map(|r| i32::from(r >= 4 - n));
+            let d: [f32; 4] =
+                std::array::from_fn(|i| d0[i] - offset[i] as f32 + n as f32 * SIMPLEX_UNSKEW_4D);
+            let t = 0.6 - dot_4d(d, d);
+            if t <= 0.0 {
+                return 0.0;
+            }
+            let [x, y, z, w] = std::array::from_fn(|i| (cell[i] + offset[i]) as u32);
+            let g = gradient_4d(hash(x ^ hash(y ^ hash(z ^ hash(w ^ self.seed)))));
+            t * t * t * t * dot_4d(g, d)
+        };
+        27.0 * (0..=4).map(corner).sum::<f32>()
+    }
+
+    fn worley(&self, [x, y]: [f32; 2]) -> f32 {
+        let cell = [x.floor() as i32, y.floor() as i32];
+        let mut nearest = f32::MAX;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let neighbour = [cell[0] + dx, cell[1] + dy];
+                let h = self.hash(neighbour);
+                let point = [
+                    neighbour[0] as f32 + unit(h),
+                    neighbour[1] as f32 + unit(hash(h)),
+                ];
+                let d = [point[0] - x, point[1] - y];
+                nearest = nearest.min(dot(d, d));
+            }
+        }
+        nearest.sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(settings: serde_json::Value) -> NoiseParams {
+        serde_json::from_value(settings).unwrap()
+    }
+
+    #[test]
+    fn lacunarity_and_gain_are_checked() {
+        let defaults = params(serde_json::json!({}));
+        for (lacunarity, gain) in [
+            (0.5, 0.5),
+            (f32::INFINITY, 0.5),
+            (f32::NAN, 0.5),
+            (2.0, 0.0),
+            (2.0, -0.5),
+            (2.0, f32::NAN),
+        ] {
+            let params = NoiseParams {
+                lacunarity,
+                gain,
+                ..defaults.clone()
+            };
+            assert!(params.octaves().is_err(), "{} {}", lacunarity, gain);
+        }
+        let params = NoiseParams {
+            octaves: 3,
+            lacunarity: 1.0,
+            gain: 2.0,
+            ..defaults
+        };
+        assert_eq!(params.octaves().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn tileable_noise_repeats_after_its_period() {
+        for kind in [NoiseKind::Value, NoiseKind::Perlin, NoiseKind::Simplex] {
+            let lattice = Lattice {
+                seed: 7,
+                period: Some(5),
+            };
+            for p in [[0.25, 0.5], [1.7, 3.2], [4.9, 0.1]] {
+                let value = lattice.noise(kind, p);
+                for wrapped in [[p[0] + 5.0, p[1]], [p[0], p[1] - 5.0]] {
+                    let delta = (lattice.noise(kind, wrapped) - value).abs();
+                    assert!(delta < 1e-4, "{:?} at {:?} is off by {}", kind, p, delta);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simplex_noise_spans_the_range_flat_or_on_a_torus() {
+        for period in [None, Some(8)] {
+            let lattice = Lattice { seed: 1, period };
+            let values: Vec<f32> = (0..64 * 64)
+                .map(|i| [(i % 64) as f32 / 8.0, (i / 64) as f32 / 8.0])
+                .map(|p| lattice.noise(NoiseKind::Simplex, p))
+                .collect();
+            let low = values.iter().copied().fold(1.0, f32::min);
+            let high = values.iter().copied().fold(0.0, f32::max);
+            assert!(low < 0.25 && high > 0.75, "{:?}: {}..{}", period, low, high);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::{
+    single_output, CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef,
+    ParamKind, PortDef,
+};
+use crate::error::EngineError;
+use crate::graph::DEFAULT_INPUT;
+use crate::state::OutputEncoding;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct OutputParams {
+    /// How the result is encoded when it is read back.
+    #[serde(default)]
+    pub encoding: OutputEncoding,
+}
+
+/// Marks the texture that is read back as the graph's result.
+pub struct OutputProcessor;
+
+impl NodeProcessor for OutputProcessor {
+    fn node_type(&self) -> &'static str {
+        "outputNode"
+    }
+
+    fn inputs(&self) -> &'static [PortDef] {
+        &[PortDef {
+            handle: DEFAULT_INPUT,
+            label: "In",
+        }]
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[ParamDef {
+            name: "encoding",
+            kind: ParamKind::Choice {
+                options: OutputEncoding::NAMES,
+                default: "srgb8",
+            },
+        }]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        Ok(single_output(ctx.input(DEFAULT_INPUT)?))
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        Ok(single_output(ctx.input(DEFAULT_INPUT)?))
+    }
+}
@@ -0,0 +1,514 @@
+use super::{
+    CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef, ParamKind, PortDef,
+};
+use crate::backend::cpu::CpuImage;
+use crate::error::EngineError;
+use crate::graph::{ColorData, NodeKind, Point, Size, DEFAULT_OUTPUT};
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PatternKind {
+    /// Cells alternating between the color and the background.
+    #[default]
+    Checker,
+    /// Parallel stripes, `width` apart or `height` apart when horizontal.
+    Stripes,
+    /// Rows of bricks, every other row shifted by half a brick.
+    Bricks,
+    /// A honeycomb of hexagons in rows like the bricks.
+    Hex,
+    /// An ellipse in every cell, a circle if the cells are square.
+    Dots,
+}
+
+impl PatternKind {
+    /// The names the kinds are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["checker", "stripes", "bricks", "hex", "dots"];
+}
+
+/// Which way stripes run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    #[default]
+    Vertical,
+    Horizontal,
+    /// From the bottom left to the top right, repeating every `width`
+    /// pixels in both directions.
+    Diagonal,
+}
+
+impl Direction {
+    /// The names the directions are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["vertical", "horizontal", "diagonal"];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PatternParams {
+    #[serde(default)]
+    pub pattern: PatternKind,
+    /// Width of a cell in pixels.
+    #[serde(default = "PatternParams::default_period")]
+    pub width: u32,
+    /// Height of a cell in pixels.
+    #[serde(default = "PatternParams::default_period")]
+    pub height: u32,
+    /// Pixels of mortar along the right and bottom of every cell or
+    /// hexagon, shrinking the color to leave the background showing.
+    #[serde(default = "PatternParams::default_gap")]
+    pub gap: u32,
+    /// Moves the whole pattern right and down by this many pixels.
+    #[serde(default)]
+    pub offset: Point,
+    /// Only used by stripes.
+    #[serde(default)]
+    pub direction: Direction,
+    #[serde(default = "PatternParams::default_color")]
+    pub color: ColorData,
+    #[serde(default = "PatternParams::default_background")]
+    pub background: ColorData,
+}
+
+impl PatternParams {
+    fn default_period() -> u32 {
+        8
+    }
+
+    fn default_gap() -> u32 {
+        1
+    }
+
+    fn default_color() -> ColorData {
+        ColorData {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 1.0,
+        }
+    }
+
+    fn default_background() -> ColorData {
+        ColorData {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 1.0,
+        }
+    }
+
+    /// One repeat of the pattern: whether each of its pixels is colored,
+    /// row by row, and its size.
+    fn tile(&self) -> Result<(Vec<bool>, Size), String> {
+        for (name, value) in [("Width", self.width), ("Height", self.height)] {
+            if !(1..=MAX_PERIOD).contains(&value) {
+                return Err(format!("{} must be between 1 and {}", name, MAX_PERIOD));
+            }
+        }
+        if self.gap > MAX_PERIOD {
+            return Err(format!("Gap must be between 0 and {}", MAX_PERIOD));
+        }
+
+        let (w, h, gap) = (
+            i64::from(self.width),
+            i64::from(self.height),
+            i64::from(self.gap),
+        );
+        // Whether a pixel `along` pixels into a cell `period` pixels long
+        // is clear of the mortar at its end.
+        let clear = move |along: i64, period: i64| along < period - gap;
+        let solid = move |x: i64, y: i64| clear(x, w) && clear(y, h);
+        let (size, covered): (_, Box<dyn Fn(i64, i64) -> bool>) = match self.pattern {
+            PatternKind::Checker => (
+                Size::new(2 * self.width, 2 * self.height),
+                Box::new(move |x, y| (x / w + y / h) % 2 == 0 && solid(x % w, y % h)),
+            ),
+            PatternKind::Stripes => match self.direction {
+                Direction::Vertical => {
+                    (Size::new(self.width, 1), Box::new(move |x, _| clear(x, w)))
+                }
+                Direction::Horizontal => {
+                    (Size::new(1, self.height), Box::new(move |_, y| clear(y, h)))
+                }
+                Direction::Diagonal => (
+                    Size::new(self.width, self.width),
+                    Box::new(move |x, y| clear((x + y) % w, w)),
+                ),
+            },
+            PatternKind::Bricks => (
+                Size::new(self.width, 2 * self.height),
+                Box::new(move |x, y| {
+                    let shift = if y >= h { w / 2 } else { 0 };
+                    solid((x - shift).rem_euclid(w), y % h)
+                }),
+            ),
+            // Hexagons are convex, so the square of mortar reaches into
+            // another one if any of its corners does.
+            PatternKind::Hex => (
+                Size::new(self.width, 2 * self.height),
+                Box::new(move |x, y| {
+                    let cell = hexagon(x, y, w, h);
+                    [(gap, 0), (0, gap), (gap, gap)]
+                        .iter()
+                        .all(|&(dx, dy)| hexagon(x + dx, y + dy, w, h) == cell)
+                }),
+            ),
+            PatternKind::Dots => (
+                Size::new(self.width, self.height),
+                Box::new(move |x, y| inside_ellipse(x, y, w - gap, h - gap)),
+            ),
+        };
+
+        let covered = (0..i64::from(size.height))
+            .flat_map(|y| (0..i64::from(size.width)).map(move |x| (x, y)))
+            .map(|(x, y)| covered(x, y))
+            .collect();
+        Ok((covered, size))
+    }
+
+    /// Whether each pixel of a canvas of `size` is colored, row by row: the
+    /// tile repeated from `offset`.
+    fn coverage(&self, size: Size) -> Result<Vec<bool>, String> {
+        let (covered, tile) = self.tile()?;
+        let [ox, oy] = offset(self.offset, tile).map(|o| o as u32);
+        Ok((0..size.height)
+            .flat_map(|y| (0..size.width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let x = (x + tile.width - ox) % tile.width;
+                let y = (y + tile.height - oy) % tile.height;
+                covered[(y * tile.width + x) as usize]
+            })
+            .collect())
+    }
+}
+
+const MAX_PERIOD: u32 = 1024;
+
+/// Whether the center of pixel `x`, `y` lies in the ellipse filling the box
+/// of `w` by `h` pixels at the origin. Worked out in half pixels, so it is
+/// exact.
+fn inside_ellipse(x: i64, y: i64, w: i64, h: i64) -> bool {
+    if w <= 0 || h <= 0 {
+        return false;
+    }
+    let (dx, dy) = (2 * x + 1 - w, 2 * y + 1 - h);
+    dx * dx * h * h + dy * dy * w * w <= w * w * h * h
+}
+
+/// The hexagon the center of pixel `x`, `y` falls in, by its row and
+/// column, in a honeycomb whose hexagons are centered in cells of `w` by `h`
+/// pixels and whose odd rows are shifted right by half a cell. Each hexagon
+/// is the area closer to its center than to any other, worked out in half
+/// pixels so it is exact. Ties go to the row above, then the column to the
+/// left.
+fn hexagon(x: i64, y: i64, w: i64, h: i64) -> (i64, i64) {
+    let (px, py) = (2 * x + 1, 2 * y + 1);
+    let (row, column) = (y.div_euclid(h), x.div_euclid(w));
+    (row - 1..=row + 1)
+        .flat_map(|r| (column - 1..=column + 1).map(move |c| (r, c)))
+        .min_by_key(|&(r, c)| {
+            let cx = (2 * c + 1) * w + r.rem_euclid(2) * w;
+            let cy = (2 * r + 1) * h;
+            (px - cx).pow(2) + (py - cy).pow(2)
+        })
+        .expect("there are always candidates")
+}
+
+/// `offset` wrapped into one repeat of a `tile`.
+fn offset(offset: Point, tile: Size) -> [i32; 2] {
+    [
+        offset.x.rem_euclid(tile.width as i32),
+        offset.y.rem_euclid(tile.height as i32),
+    ]
+}
+
+/// Mirrors `PatternUniforms` in the pattern shader.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PatternUniforms {
+    color: [f32; 4],
+    background: [f32; 4],
+    offset: [i32; 2],
+    _padding: [i32; 2],
+}
+
+/// Repeats checks, stripes, bricks, hexagons or dots across the canvas.
+///
+/// Cells are whole pixels, so a pattern tiles cleanly on any canvas whose
+/// size is a multiple of its period. One repeat of the pattern is worked out
+/// on the CPU with integer math for both backends and the GPU only tiles
+/// it, which keeps the backends identical to the pixel.
+///
+/// The `out` output is the color on the background, the `mask` output the
+/// colored pixels in white on black.
+pub struct PatternProcessor;
+
+impl NodeProcessor for PatternProcessor {
+    fn node_type(&self) -> &'static str {
+        "patternNode"
+    }
+
+    fn outputs(&self) -> &'static [PortDef] {
+        &[
+            PortDef {
+                handle: DEFAULT_OUTPUT,
+                label: "Color",
+            },
+            PortDef {
+                handle: "mask",
+                label: "Mask",
+            },
+        ]
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[
+            ParamDef {
+                name: "pattern",
+                kind: ParamKind::Choice {
+                    options: PatternKind::NAMES,
+                    default: "checker",
+                },
+            },
+            ParamDef {
+                name: "width",
+                kind: ParamKind::Int {
+                    min: 1,
+                    max: MAX_PERIOD,
+                    default: 8,
+                },
+            },
+            ParamDef {
+                name: "height",
+                kind: ParamKind::Int {
+                    min: 1,
+                    max: MAX_PERIOD,
+                    default: 8,
+                },
+            },
+            ParamDef {
+                name: "gap",
+                kind: ParamKind::Int {
+                    min: 0,
+                    max: MAX_PERIOD,
+                    default: 1,
+                },
+            },
+            ParamDef {
+                name: "offset",
+                kind: ParamKind::Point {
+                    default: Some([0, 0]),
+                },
+            },
+            ParamDef {
+                name: "direction",
+                kind: ParamKind::Choice {
+                    options: Direction::NAMES,
+                    default: "vertical",
+                },
+            },
+            ParamDef {
+                name: "color",
+                kind: ParamKind::Color,
+            },
+            ParamDef {
+                name: "background",
+                kind: ParamKind::Color,
+            },
+        ]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let NodeKind::Pattern(params) = &node.kind else {
+            return Err(ctx.error("not a pattern node"));
+        };
+        let (covered, tile_size) = params.tile().map_err(|e| ctx.error(e))?;
+
+        let device = ctx.device();
+        let tile = device.create_texture_with_data(
+            &ctx.render_context().queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Pattern Tile"),
+                size: tile_size.extent(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &covered
+                .iter()
+                .map(|&c| u8::from(c) * 255)
+                .collect::<Vec<_>>(),
+        );
+        let tile_view = tile.create_view(&wgpu::TextureViewDescriptor::default());
+        let uniforms = PatternUniforms {
+            color: params.color.to_rgba(ctx.color_space()),
+            background: params.background.to_rgba(ctx.color_space()),
+            offset: offset(params.offset, tile_size),
+            _padding: [0; 2],
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pattern Buffer"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bindings = [
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&tile_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+        ];
+
+        let mut outputs = Outputs::new();
+        for (handle, entry_point) in [(DEFAULT_OUTPUT, "cs_color"), ("mask", "cs_mask")] {
+            let pipeline = ctx
+                .render_context()
+                .pipelines
+                .compute_pipeline(device, &shaders::PATTERN, entry_point, ctx.format())
+                .map_err(|e| ctx.error(e))?;
+            let texture = ctx.dispatch(
+                &format!("Pattern {} {}", node.label, handle),
+                &pipeline,
+                &bindings,
+            )?;
+            outputs.insert(handle.to_string(), texture);
+        }
+        Ok(outputs)
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Pattern(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a pattern node"));
+        };
+        let size = ctx.size;
+        let covered = params.coverage(size).map_err(|e| ctx.error(e))?;
+
+        let space = ctx.color_space();
+        let (color, background) = (
+            params.color.to_rgba(space),
+            params.background.to_rgba(space),
+        );
+        let at = |x: u32, y: u32| covered[(y * size.width + x) as usize];
+        let image = CpuImage::from_fn(size, space, |x, y| match at(x, y) {
+            true => color,
+            false => background,
+        });
+        let mask = CpuImage::from_fn(size, space, |x, y| {
+            let value = if at(x, y) { 1.0 } else { 0.0 };
+            [value, value, value, 1.0]
+        });
+        Ok(CpuOutputs::from([
+            (DEFAULT_OUTPUT.to_string(), Arc::new(image)),
+            ("mask".to_string(), Arc::new(mask)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(settings: serde_json::Value) -> PatternParams {
+        serde_json::from_value(settings).unwrap()
+    }
+
+    #[test]
+    fn sizes_above_the_maximum_period_are_rejected() {
+        for settings in [
+            json!({ "width": 0 }),
+            json!({ "width": MAX_PERIOD + 1 }),
+            json!({ "height": MAX_PERIOD + 1 }),
+            json!({ "gap": MAX_PERIOD + 1 }),
+        ] {
+            assert!(params(settings.clone()).tile().is_err(), "{}", settings);
+        }
+        let largest = json!({ "width": MAX_PERIOD, "height": MAX_PERIOD, "gap": MAX_PERIOD });
+        assert!(params(largest).tile().is_ok());
+    }
+
+    #[test]
+    fn every_pattern_repeats_after_its_period() {
+        // Odd sizes and an offset, so halves and wrapping round both ways.
+        let (w, h) = (5, 3);
+        for (settings, period) in [
+            (json!({ "pattern": "checker" }), (2 * w, 2 * h)),
+            (json!({ "pattern": "stripes" }), (w, 1)),
+            (
+                json!({ "pattern": "stripes", "direction": "horizontal" }),
+                (1, h),
+            ),
+            (
+                json!({ "pattern": "stripes", "direction": "diagonal" }),
+                (w, w),
+            ),
+            (json!({ "pattern": "bricks" }), (w, 2 * h)),
+            (json!({ "pattern": "hex" }), (w, 2 * h)),
+            (json!({ "pattern": "dots" }), (w, h)),
+        ] {
+            let mut settings = settings;
+            settings["width"] = json!(w);
+            settings["height"] = json!(h);
+            settings["offset"] = json!({ "x": 2, "y": -1 });
+            let size = Size::new(3 * 2 * w, 3 * 2 * h);
+            let covered = params(settings.clone()).coverage(size).unwrap();
+            let at = |x: u32, y: u32| covered[(y * size.width + x) as usize];
+
+            assert!(
+                covered.contains(&true) && covered.contains(&false),
+                "{}",
+                settings
+            );
+            for y in 0..size.height - period.1 {
+                for x in 0..size.width - period.0 {
+                    assert_eq!(
+                        at(x, y),
+                        at(x + period.0, y),
+                        "{} at {}, {}",
+                        settings,
+                        x,
+                        y
+                    );
+                    assert_eq!(
+                        at(x, y),
+                        at(x, y + period.1),
+                        "{} at {}, {}",
+                        settings,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hex_tile_matches_the_whole_honeycomb() {
+        let (w, h, gap) = (7, 4, 1);
+        let hex = params(json!({ "pattern": "hex", "width": w, "height": h, "gap": gap }));
+        let size = Size::new(4 * w as u32, 4 * h as u32);
+        let covered = hex.coverage(size).unwrap();
+        for y in 0..i64::from(size.height) {
+            for x in 0..i64::from(size.width) {
+                let cell = hexagon(x, y, w, h);
+                let expected = [(gap, 0), (0, gap), (gap, gap)]
+                    .iter()
+                    .all(|&(dx, dy)| hexagon(x + dx, y + dy, w, h) == cell);
+                let index = (y * i64::from(size.width) + x) as usize;
+                assert_eq!(covered[index], expected, "at {}, {}", x, y);
+            }
+        }
+    }
+}
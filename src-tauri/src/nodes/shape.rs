@@ -0,0 +1,580 @@
+use super::{
+    CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, ParamDef, ParamKind, PortDef,
+};
+use crate::backend::cpu::CpuImage;
+use crate::error::EngineError;
+use crate::graph::{ColorData, NodeKind, Point, Size, DEFAULT_OUTPUT, MAX_SIZE};
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::TAU;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ShapeKind {
+    #[default]
+    Rect,
+    /// An ellipse filling the box, a circle if the box is square.
+    Ellipse,
+    /// A regular polygon inscribed in the ellipse filling the box.
+    Polygon,
+    /// A line from `from` to `to`.
+    Line,
+}
+
+impl ShapeKind {
+    /// The names the kinds are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["rect", "ellipse", "polygon", "line"];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ShapeMode {
+    #[default]
+    Fill,
+    /// Only the outline, `thickness` pixels wide, growing inwards: the
+    /// pixels of the filled shape less than `thickness` from its outline.
+    Stroke,
+}
+
+impl ShapeMode {
+    /// The names the modes are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["fill", "stroke"];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ShapeParams {
+    #[serde(default)]
+    pub shape: ShapeKind,
+    /// One corner of the shape's box, or the start of a line. Both points
+    /// are included in the shape and may lie off the canvas, by up to
+    /// [`MAX_OVERHANG`] canvas sizes.
+    #[serde(default)]
+    pub from: Point,
+    /// The opposite corner of the box, or the end of a line.
+    #[serde(default = "ShapeParams::default_to")]
+    pub to: Point,
+    /// Ignored for lines, which are always stroked.
+    #[serde(default)]
+    pub mode: ShapeMode,
+    /// Stroke width, or the side of the square brush lines are drawn with.
+    #[serde(default = "ShapeParams::default_thickness")]
+    pub thickness: u32,
+    /// Corners of a polygon.
+    #[serde(default = "ShapeParams::default_sides")]
+    pub sides: u32,
+    /// Turns a polygon clockwise, in degrees. With no rotation its first
+    /// corner points up.
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "ShapeParams::default_color")]
+    pub color: ColorData,
+}
+
+impl ShapeParams {
+    fn default_to() -> Point {
+        Point { x: 15, y: 15 }
+    }
+
+    fn default_thickness() -> u32 {
+        1
+    }
+
+    fn default_sides() -> u32 {
+        6
+    }
+
+    fn default_color() -> ColorData {
+        ColorData {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 1.0,
+        }
+    }
+
+    /// Whether each pixel of a canvas of `size` is covered, row by row.
+    fn rasterize(&self, size: Size) -> Result<Vec<bool>, String> {
+        if size.too_large() {
+            return Err(format!(
+                "A shape can be at most {}x{} pixels",
+                MAX_SIZE, MAX_SIZE
+            ));
+        }
+        if !(1..=MAX_THICKNESS).contains(&self.thickness) {
+            return Err(format!("Thickness must be between 1 and {}", MAX_THICKNESS));
+        }
+        if !(MIN_SIDES..=MAX_SIDES).contains(&self.sides) {
+            return Err(format!(
+                "A polygon must have between {} and {} sides",
+                MIN_SIDES, MAX_SIDES
+            ));
+        }
+
+        let (width, height) = (i64::from(size.width), i64::from(size.height));
+        let reachable = |extent: i64, at: i32| {
+            (-MAX_OVERHANG * extent..(MAX_OVERHANG + 1) * extent).contains(&i64::from(at))
+        };
+        for point in [self.from, self.to] {
+            if !reachable(width, point.x) || !reachable(height, point.y) {
+                return Err(format!(
+                    "Points must lie within {} canvas sizes of the canvas",
+                    MAX_OVERHANG
+                ));
+            }
+        }
+
+        let mut covered = vec![false; (width * height) as usize];
+        let mut cover = |(x, y): Pixel| {
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                covered[(y * width + x) as usize] = true;
+            }
+        };
+
+        if self.shape == ShapeKind::Line {
+            // A square brush centered on each pixel of the line, leaning to
+            // the top left for even thicknesses.
+            let thickness = i64::from(self.thickness);
+            let offset = (thickness - 1) / 2;
+            for (x, y) in line(self.from.into(), self.to.into()) {
+                let (x, y) = (x - offset, y - offset);
+                if x + thickness <= 0 || y + thickness <= 0 || x >= width || y >= height {
+                    continue;
+                }
+                for dy in 0..thickness {
+                    for dx in 0..thickness {
+                        cover((x + dx, y + dy));
+                    }
+                }
+            }
+            return Ok(covered);
+        }
+
+        let region = self.region(Bounds::new(self.from, self.to));
+        if self.mode == ShapeMode::Fill {
+            for (&y, &(left, right)) in &region.spans {
+                for x in left.max(0)..=right.min(width - 1) {
+                    cover((x, y));
+                }
+            }
+            return Ok(covered);
+        }
+
+        // A stroke is a disk of radius one less than the thickness around
+        // each outline pixel, cut to the filled shape, so it keeps its width
+        // along curves and slanted edges and a one-pixel stroke is exactly
+        // the outline. Outline pixels too far off the canvas to reach it are
+        // skipped.
+        let reach = i64::from(self.thickness) - 1;
+        let disk: Vec<(i64, i64)> = (-reach..=reach)
+            .map(|dy| (dy, ((reach * reach - dy * dy) as f64).sqrt() as i64))
+            .collect();
+        for &(x, y) in &region.outline {
+            if x + reach < 0 || y + reach < 0 || x - reach >= width || y - reach >= height {
+                continue;
+            }
+            for &(dy, half) in &disk {
+                let Some(&(left, right)) = region.spans.get(&(y + dy)) else {
+                    continue;
+                };
+                for x in (x - half).max(left).max(0)..=(x + half).min(right).min(width - 1) {
+                    cover((x, y + dy));
+                }
+            }
+        }
+        Ok(covered)
+    }
+
+    /// The shape fitted to `bounds`.
+    fn region(&self, bounds: Bounds) -> Region {
+        let outline = match self.shape {
+            ShapeKind::Rect => bounds.outline(),
+            ShapeKind::Ellipse => ellipse(bounds),
+            ShapeKind::Polygon => polygon(bounds, self.sides, self.rotation),
+            ShapeKind::Line => unreachable!("lines have no region"),
+        };
+        Region::new(outline)
+    }
+}
+
+const MAX_THICKNESS: u32 = 64;
+/// How many canvas sizes a point may lie off the canvas, which keeps the
+/// walks along outlines in proportion to the canvas.
+const MAX_OVERHANG: i64 = 4;
+const MIN_SIDES: u32 = 3;
+const MAX_SIDES: u32 = 64;
+
+/// Draws a rectangle, ellipse, regular polygon or line with integer
+/// midpoint and Bresenham rules, so edges are hard and one-pixel outlines
+/// never double up at corners or steps.
+///
+/// The `out` output is the shape in its color on transparent black, the
+/// `mask` output its coverage in white on black.
+///
+/// Rasterization is inherently a walk along the outline, so the coverage is
+/// worked out on the CPU for both backends and the GPU only colors it in.
+/// This also keeps the two backends identical to the pixel.
+pub struct ShapeProcessor;
+
+impl NodeProcessor for ShapeProcessor {
+    fn node_type(&self) -> &'static str {
+        "shapeNode"
+    }
+
+    fn outputs(&self) -> &'static [PortDef] {
+        &[
+            PortDef {
+                handle: DEFAULT_OUTPUT,
+                label: "Color",
+            },
+            PortDef {
+                handle: "mask",
+                label: "Mask",
+            },
+        ]
+    }
+
+    fn params(&self) -> &'static [ParamDef] {
+        &[
+            ParamDef {
+                name: "shape",
+                kind: ParamKind::Choice {
+                    options: ShapeKind::NAMES,
+                    default: "rect",
+                },
+            },
+            ParamDef {
+                name: "from",
+                kind: ParamKind::Point {
+                    default: Some([0, 0]),
+                },
+            },
+            ParamDef {
+                name: "to",
+                kind: ParamKind::Point {
+                    default: Some([15, 15]),
+                },
+            },
+            ParamDef {
+                name: "mode",
+                kind: ParamKind::Choice {
+                    options: ShapeMode::NAMES,
+                    default: "fill",
+                },
+            },
+            ParamDef {
+                name: "thickness",
+                kind: ParamKind::Int {
+                    min: 1,
+                    max: MAX_THICKNESS,
+                    default: 1,
+                },
+            },
+            ParamDef {
+                name: "sides",
+                kind: ParamKind::Int {
+                    min: MIN_SIDES,
+                    max: MAX_SIDES,
+                    default: 6,
+                },
+            },
+            ParamDef {
+                name: "rotation",
+                kind: ParamKind::Float {
+                    min: 0.0,
+                    max: 360.0,
+                    default: 0.0,
+                },
+            },
+            ParamDef {
+                name: "color",
+                kind: ParamKind::Color,
+            },
+        ]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let NodeKind::Shape(params) = &node.kind else {
+            return Err(ctx.error("not a shape node"));
+        };
+        let covered = params.rasterize(ctx.size).map_err(|e| ctx.error(e))?;
+
+        let device = ctx.device();
+        let coverage = device.create_texture_with_data(
+            &ctx.render_context().queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Shape Coverage"),
+                size: ctx.size.extent(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &covered
+                .iter()
+                .map(|&c| u8::from(c) * 255)
+                .collect::<Vec<_>>(),
+        );
+        let coverage_view = coverage.create_view(&wgpu::TextureViewDescriptor::default());
+        let color = params.color.to_rgba(ctx.color_space());
+        let color_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shape Color Buffer"),
+            contents: bytemuck::cast_slice(&color),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bindings = [
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&coverage_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: color_buffer.as_entire_binding(),
+            },
+        ];
+
+        let mut outputs = Outputs::new();
+        for (handle, entry_point) in [(DEFAULT_OUTPUT, "cs_color"), ("mask", "cs_mask")] {
+            let pipeline = ctx
+                .render_context()
+                .pipelines
+                .compute_pipeline(device, &shaders::SHAPE, entry_point, ctx.format())
+                .map_err(|e| ctx.error(e))?;
+            let texture = ctx.dispatch(
+                &format!("Shape {} {}", node.label, handle),
+                &pipeline,
+                &bindings,
+            )?;
+            outputs.insert(handle.to_string(), texture);
+        }
+        Ok(outputs)
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let NodeKind::Shape(params) = &ctx.node().kind else {
+            return Err(ctx.error("not a shape node"));
+        };
+        let covered = params.rasterize(ctx.size).map_err(|e| ctx.error(e))?;
+
+        let (size, space) = (ctx.size, ctx.color_space());
+        let color = params.color.to_rgba(space);
+        let at = |x: u32, y: u32| covered[(y * size.width + x) as usize];
+        let image = CpuImage::from_fn(size, space, |x, y| match at(x, y) {
+            true => color,
+            false => [0.0; 4],
+        });
+        let mask = CpuImage::from_fn(size, space, |x, y| {
+            let value = if at(x, y) { 1.0 } else { 0.0 };
+            [value, value, value, 1.0]
+        });
+        Ok(CpuOutputs::from([
+            (DEFAULT_OUTPUT.to_string(), Arc::new(image)),
+            ("mask".to_string(), Arc::new(mask)),
+        ]))
+    }
+}
+
+type Pixel = (i64, i64);
+
+impl From<Point> for Pixel {
+    fn from(point: Point) -> Self {
+        (i64::from(point.x), i64::from(point.y))
+    }
+}
+
+/// The box a shape is fitted to, corners included.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+}
+
+impl Bounds {
+    fn new(a: Point, b: Point) -> Self {
+        Self {
+            left: i64::from(a.x.min(b.x)),
+            top: i64::from(a.y.min(b.y)),
+            right: i64::from(a.x.max(b.x)),
+            bottom: i64::from(a.y.max(b.y)),
+        }
+    }
+
+    /// The pixels along the edges of the box.
+    fn outline(self) -> Vec<Pixel> {
+        let horizontal = (self.left..=self.right).flat_map(|x| [(x, self.top), (x, self.bottom)]);
+        let vertical = (self.top..=self.bottom).flat_map(|y| [(self.left, y), (self.right, y)]);
+        horizontal.chain(vertical).collect()
+    }
+}
+
+/// A convex shape: its one-pixel outline and, for every row it touches, the
+/// span from its leftmost to its rightmost outline pixel.
+struct Region {
+    outline: HashSet<Pixel>,
+    spans: HashMap<i64, (i64, i64)>,
+}
+
+impl Region {
+    fn new(outline: Vec<Pixel>) -> Self {
+        let mut spans: HashMap<i64, (i64, i64)> = HashMap::new();
+        for &(x, y) in &outline {
+            let span = spans.entry(y).or_insert((x, x));
+            *span = (span.0.min(x), span.1.max(x));
+        }
+        Self {
+            outline: outline.into_iter().collect(),
+            spans,
+        }
+    }
+}
+
+/// The pixels of the line from `from` to `to`, both included, by
+/// Bresenham's algorithm.
+fn line(from: Pixel, to: Pixel) -> Vec<Pixel> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let (sx, sy) = ((to.0 - x).signum(), (to.1 - y).signum());
+    let mut err = dx + dy;
+    let mut pixels = Vec::new();
+    loop {
+        pixels.push((x, y));
+        if (x, y) == to {
+            return pixels;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// The outline of the ellipse filling `bounds`, by the midpoint algorithm
+/// in the form Zingl gives for boxes of any size, even ones included.
+///
+/// The error terms grow with the cube of the box size and are kept in
+/// `i128`, which cannot overflow for any box of `i32` corners.
+fn ellipse(bounds: Bounds) -> Vec<Pixel> {
+    let a = bounds.right - bounds.left;
+    let b = bounds.bottom - bounds.top;
+    let b1 = b & 1;
+    let (mut x0, mut x1) = (bounds.left, bounds.right);
+    let mut y0 = bounds.top + (b + 1) / 2;
+    let mut y1 = y0 - b1;
+
+    // Error increments, and the error of the first step.
+    let (a, b, b1) = (i128::from(a), i128::from(b), i128::from(b1));
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut err = dx + dy + b1 * a * a;
+    let (ddx, ddy) = (8 * b * b, 8 * a * a);
+
+    let mut pixels = Vec::new();
+    while x0 <= x1 {
+        pixels.extend([(x1, y0), (x0, y0), (x0, y1), (x1, y1)]);
+        let e2 = 2 * err;
+        if e2 <= dy {
+            y0 += 1;
+            y1 -= 1;
+            dy += ddy;
+            err += dy;
+        }
+        if e2 >= dx || 2 * err > dy {
+            x0 += 1;
+            x1 -= 1;
+            dx += ddx;
+            err += dx;
+        }
+    }
+    // Flat ellipses stop before reaching their tips.
+    while i128::from(y0 - y1) <= b {
+        pixels.extend([(x0 - 1, y0), (x1 + 1, y0), (x0 - 1, y1), (x1 + 1, y1)]);
+        y0 += 1;
+        y1 -= 1;
+    }
+    pixels
+}
+
+/// The outline of the regular polygon with `sides` corners inscribed in the
+/// ellipse filling `bounds`, its corners rounded to pixels and joined by
+/// Bresenham lines.
+fn polygon(bounds: Bounds, sides: u32, rotation: f32) -> Vec<Pixel> {
+    let center = (
+        (bounds.left + bounds.right) as f64 / 2.0,
+        (bounds.top + bounds.bottom) as f64 / 2.0,
+    );
+    let radius = (
+        (bounds.right - bounds.left) as f64 / 2.0,
+        (bounds.bottom - bounds.top) as f64 / 2.0,
+    );
+    let start = f64::from(rotation).to_radians() - TAU / 4.0;
+    let corners: Vec<Pixel> = (0..sides)
+        .map(|i| {
+            let angle = start + TAU * f64::from(i) / f64::from(sides);
+            (
+                (center.0 + radius.0 * angle.cos()).round() as i64,
+                (center.1 + radius.1 * angle.sin()).round() as i64,
+            )
+        })
+        .collect();
+    corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .flat_map(|(&from, &to)| line(from, to))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(from: Point, to: Point) -> ShapeParams {
+        serde_json::from_value(serde_json::json!({
+            "shape": "ellipse",
+            "from": from,
+            "to": to,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn points_far_off_the_canvas_are_rejected() {
+        let size = Size::new(16, 8);
+        let far = params(Point { x: 0, y: 0 }, Point { x: 80, y: 4 });
+        assert!(far.rasterize(size).is_err());
+        let far = params(Point { x: 0, y: -33 }, Point { x: 4, y: 4 });
+        assert!(far.rasterize(size).is_err());
+        let huge = params(Point { x: i32::MIN, y: 0 }, Point { x: i32::MAX, y: 4 });
+        assert!(huge.rasterize(size).is_err());
+    }
+
+    #[test]
+    fn canvas_above_the_size_limit_is_rejected_before_allocating() {
+        let shape = params(Point { x: 0, y: 0 }, Point { x: 4, y: 4 });
+        assert!(shape.rasterize(Size::new(u32::MAX, u32::MAX)).is_err());
+        assert!(shape.rasterize(Size::new(MAX_SIZE + 1, 1)).is_err());
+        assert!(shape.rasterize(Size::new(MAX_SIZE, 1)).is_ok());
+    }
+
+    #[test]
+    fn points_a_few_canvases_off_are_drawn() {
+        let size = Size::new(16, 8);
+        let wide = params(Point { x: -64, y: -32 }, Point { x: 79, y: 39 });
+        let covered = wide.rasterize(size).unwrap();
+        assert!(covered.iter().all(|&c| c));
+    }
+}
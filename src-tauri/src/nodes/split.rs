@@ -0,0 +1,113 @@
+use super::{CpuContext, CpuOutputs, NodeContext, NodeProcessor, Outputs, PortDef};
+use crate::error::EngineError;
+use crate::graph::DEFAULT_INPUT;
+use crate::shaders;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SplitParams {}
+
+/// Splits its input into one grayscale image per channel.
+pub struct SplitProcessor;
+
+const CHANNELS: [(&str, &str); 4] = [("r", "fs_r"), ("g", "fs_g"), ("b", "fs_b"), ("a", "fs_a")];
+
+impl NodeProcessor for SplitProcessor {
+    fn node_type(&self) -> &'static str {
+        "splitNode"
+    }
+
+    fn inputs(&self) -> &'static [PortDef] {
+        &[PortDef {
+            handle: DEFAULT_INPUT,
+            label: "In",
+        }]
+    }
+
+    fn outputs(&self) -> &'static [PortDef] {
+        &[
+            PortDef {
+                handle: "r",
+                label: "R",
+            },
+            PortDef {
+                handle: "g",
+                label: "G",
+            },
+            PortDef {
+                handle: "b",
+                label: "B",
+            },
+            PortDef {
+                handle: "a",
+                label: "A",
+            },
+        ]
+    }
+
+    fn process(&self, ctx: &mut NodeContext) -> Result<Outputs, EngineError> {
+        let node = ctx.node();
+        let source = ctx.input(DEFAULT_INPUT)?;
+        let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = ctx.sampler(DEFAULT_INPUT);
+
+        let device = ctx.device();
+        let sampling = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Split Sampling Buffer"),
+            contents: bytemuck::cast_slice(&ctx.sampling(DEFAULT_INPUT)),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let mut outputs = Outputs::new();
+        for (handle, entry_point) in CHANNELS {
+            let pipeline = ctx
+                .render_context()
+                .pipelines
+                .render_pipeline(device, &shaders::SPLIT, entry_point, ctx.format())
+                .map_err(|e| ctx.error(e))?;
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Split Bind Group"),
+                layout: &pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&source_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: sampling.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let texture = ctx.create_texture(&format!("Split {} {}", node.label, handle))?;
+            ctx.draw_fullscreen("Split Pass", &pipeline.pipeline, &bind_group, &texture);
+            outputs.insert(handle.to_string(), texture);
+        }
+
+        Ok(outputs)
+    }
+
+    fn process_cpu(&self, ctx: &mut CpuContext) -> Result<CpuOutputs, EngineError> {
+        let source = ctx.input(DEFAULT_INPUT)?;
+
+        let mut outputs = CpuOutputs::new();
+        for (channel, (handle, _)) in CHANNELS.into_iter().enumerate() {
+            let image = ctx.shade(|uv| {
+                let value = ctx.sample(&source, DEFAULT_INPUT, uv)[channel];
+                [value, value, value, 1.0]
+            });
+            outputs.insert(handle.to_string(), image);
+        }
+
+        Ok(outputs)
+    }
+}
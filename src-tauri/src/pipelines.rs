@@ -0,0 +1,266 @@
+use crate::shaders::{
+    ComputeShaderDef, ShaderDef, COMPUTE_PRELUDE, FULLSCREEN_VERTEX, SAMPLE_INPUT,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    shader: &'static str,
+    entry_point: &'static str,
+    format: wgpu::TextureFormat,
+}
+
+/// A compiled render pipeline and the layout its bind groups are made from.
+pub struct RenderPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub bind_group_layout: Arc<wgpu::BindGroupLayout>,
+}
+
+/// A compiled compute pipeline and the layout its bind groups are made from,
+/// starting with the output texture at binding 0.
+pub struct ComputePipeline {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: Arc<wgpu::BindGroupLayout>,
+}
+
+/// Compiles pipelines the first time a node type asks for them and keeps
+/// them for the lifetime of the device.
+///
+/// Failures are cached too, keyed by shader id, so a broken shader is
+/// compiled once and then reported the same way on every later request.
+#[derive(Default)]
+pub struct PipelineCache {
+    modules: RefCell<HashMap<&'static str, Result<Arc<wgpu::ShaderModule>, String>>>,
+    layouts: RefCell<HashMap<&'static str, Arc<wgpu::BindGroupLayout>>>,
+    render: RefCell<HashMap<PipelineKey, Result<Arc<RenderPipeline>, String>>>,
+    compute: RefCell<HashMap<PipelineKey, Result<Arc<ComputePipeline>, String>>>,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the full-screen render pipeline running `entry_point` of
+    /// `shader` into targets of `format`.
+    pub fn render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        shader: &ShaderDef,
+        entry_point: &'static str,
+        format: wgpu::TextureFormat,
+    ) -> Result<Arc<RenderPipeline>, String> {
+        let key = PipelineKey {
+            shader: shader.id,
+            entry_point,
+            format,
+        };
+        if let Some(cached) = self.render.borrow().get(&key) {
+            return cached.clone();
+        }
+
+        let result = self.build_render_pipeline(device, shader, entry_point, format);
+        self.render.borrow_mut().insert(key, result.clone());
+        result
+    }
+
+    /// Returns the pipeline running compute entry point `entry_point` of
+    /// `shader`, writing storage textures of `format`.
+    pub fn compute_pipeline(
+        &self,
+        device: &wgpu::Device,
+        shader: &ComputeShaderDef,
+        entry_point: &'static str,
+        format: wgpu::TextureFormat,
+    ) -> Result<Arc<ComputePipeline>, String> {
+        let key = PipelineKey {
+            shader: shader.id,
+            entry_point,
+            format,
+        };
+        if let Some(cached) = self.compute.borrow().get(&key) {
+            return cached.clone();
+        }
+
+        let result = build_compute_pipeline(device, shader, entry_point, format);
+        self.compute.borrow_mut().insert(key, result.clone());
+        result
+    }
+
+    fn build_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        shader: &ShaderDef,
+        entry_point: &'static str,
+        format: wgpu::TextureFormat,
+    ) -> Result<Arc<RenderPipeline>, String> {
+        let module = self.module(device, shader)?;
+        let bind_group_layout = self.layout(device, shader);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(shader.id),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(shader.id),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(format!(
+                "Pipeline '{}' ({}, {:?}) is invalid: {}",
+                shader.id, entry_point, format, error
+            ));
+        }
+
+        Ok(Arc::new(RenderPipeline {
+            pipeline,
+            bind_group_layout,
+        }))
+    }
+
+    fn module(
+        &self,
+        device: &wgpu::Device,
+        shader: &ShaderDef,
+    ) -> Result<Arc<wgpu::ShaderModule>, String> {
+        if let Some(cached) = self.modules.borrow().get(shader.id) {
+            return cached.clone();
+        }
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(shader.id),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{}{}", FULLSCREEN_VERTEX, SAMPLE_INPUT, shader.source).into(),
+            ),
+        });
+        let result = match pollster::block_on(device.pop_error_scope()) {
+            Some(error) => Err(format!(
+                "Shader '{}' failed to compile: {}",
+                shader.id, error
+            )),
+            None => Ok(Arc::new(module)),
+        };
+
+        self.modules.borrow_mut().insert(shader.id, result.clone());
+        result
+    }
+
+    fn layout(&self, device: &wgpu::Device, shader: &ShaderDef) -> Arc<wgpu::BindGroupLayout> {
+        self.layouts
+            .borrow_mut()
+            .entry(shader.id)
+            .or_insert_with(|| {
+                Arc::new(
+                    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: Some(shader.id),
+                        entries: shader.bindings,
+                    }),
+                )
+            })
+            .clone()
+    }
+}
+
+/// The WGSL name of a storage texture format.
+fn wgsl_storage_format(format: wgpu::TextureFormat) -> Result<&'static str, String> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm => Ok("rgba8unorm"),
+        wgpu::TextureFormat::Rgba16Float => Ok("rgba16float"),
+        other => Err(format!("{:?} is not supported for storage textures", other)),
+    }
+}
+
+/// Builds a compute pipeline. Modules and layouts depend on the output
+/// format, so unlike render pipelines they are not shared between pipelines.
+fn build_compute_pipeline(
+    device: &wgpu::Device,
+    shader: &ComputeShaderDef,
+    entry_point: &'static str,
+    format: wgpu::TextureFormat,
+) -> Result<Arc<ComputePipeline>, String> {
+    let prelude = COMPUTE_PRELUDE.replace("{format}", wgsl_storage_format(format)?);
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(shader.id),
+        source: wgpu::ShaderSource::Wgsl(format!("{}{}", prelude, shader.source).into()),
+    });
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        return Err(format!(
+            "Shader '{}' failed to compile: {}",
+            shader.id, error
+        ));
+    }
+
+    let mut entries = vec![wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        count: None,
+    }];
+    entries.extend_from_slice(shader.bindings);
+    let bind_group_layout = Arc::new(device.create_bind_group_layout(
+        &wgpu::BindGroupLayoutDescriptor {
+            label: Some(shader.id),
+            entries: &entries,
+        },
+    ));
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(shader.id),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(shader.id),
+        layout: Some(&pipeline_layout),
+        module: &module,
+        entry_point,
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    });
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        return Err(format!(
+            "Pipeline '{}' ({}, {:?}) is invalid: {}",
+            shader.id, entry_point, format, error
+        ));
+    }
+
+    Ok(Arc::new(ComputePipeline {
+        pipeline,
+        bind_group_layout,
+    }))
+}
@@ -0,0 +1,185 @@
+use crate::color_space::ColorSpace;
+use crate::error::EngineError;
+use crate::graph::Size;
+use crate::samplers::SamplerSettings;
+use crate::shaders;
+use crate::state::{OutputEncoding, RenderContext, RenderedImage};
+use crate::textures::TextureKey;
+use std::sync::Arc;
+
+/// The output stage: a blit of a node output into a texture in the requested
+/// encoding, copied into a buffer that is mapped once the encoder has been
+/// submitted.
+pub struct Readback {
+    texture: Arc<wgpu::Texture>,
+    buffer: wgpu::Buffer,
+    size: Size,
+    encoding: OutputEncoding,
+    padded_bytes_per_row: u32,
+}
+
+/// The target format and entry point of the output shader for `encoding`
+/// from working values in `space`. Gamma-space values already are sRGB, so
+/// only linear ones are encoded.
+fn output_stage(
+    encoding: OutputEncoding,
+    space: ColorSpace,
+) -> (wgpu::TextureFormat, &'static str) {
+    match encoding {
+        OutputEncoding::Srgb8 if space == ColorSpace::Linear => {
+            (wgpu::TextureFormat::Rgba8UnormSrgb, "fs_copy")
+        }
+        OutputEncoding::Rgba8 | OutputEncoding::Srgb8 => {
+            (wgpu::TextureFormat::Rgba8Unorm, "fs_copy")
+        }
+        OutputEncoding::Gray8 => (wgpu::TextureFormat::R8Unorm, "fs_gray"),
+        OutputEncoding::Rgba16f => (wgpu::TextureFormat::Rgba16Float, "fs_copy"),
+    }
+}
+
+impl Readback {
+    /// Records blitting `texture`, holding values in `space`, into a texture
+    /// of `size` in `encoding` and copying that into a mappable buffer.
+    /// `size` may be smaller than the texture, in which case it is scaled
+    /// down with nearest sampling.
+    pub fn record(
+        context: &RenderContext,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        size: Size,
+        encoding: OutputEncoding,
+        space: ColorSpace,
+    ) -> Result<Self, EngineError> {
+        let device = &context.device;
+        let source_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (format, entry_point) = output_stage(encoding, space);
+
+        let result_texture = context.textures.acquire(
+            device,
+            "Readback Texture",
+            TextureKey {
+                size,
+                format,
+                usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            },
+        );
+        let result_view = result_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let blit = context
+            .pipelines
+            .render_pipeline(device, &shaders::OUTPUT, entry_point, format)
+            .map_err(EngineError::gpu)?;
+
+        // Nearest, so scaled-down previews keep hard pixel edges.
+        let sampler = context.samplers.get(device, SamplerSettings::default());
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Output Bind Group"),
+            layout: &blit.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Output Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &result_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&blit.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+
+        // Buffer rows must be padded to COPY_BYTES_PER_ROW_ALIGNMENT, which is
+        // stripped again once the buffer is mapped.
+        let unpadded_bytes_per_row = encoding.bytes_per_pixel() * size.width;
+        let padded_bytes_per_row = unpadded_bytes_per_row
+            .div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            label: Some("Output Buffer"),
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &result_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size.extent(),
+        );
+
+        Ok(Self {
+            texture: result_texture,
+            buffer,
+            size,
+            encoding,
+            padded_bytes_per_row,
+        })
+    }
+
+    /// Maps the buffer and returns its pixels, handing the intermediate
+    /// texture back to the pool. The encoder the copy was recorded into must
+    /// have been submitted.
+    pub fn read(self, context: &RenderContext) -> Result<RenderedImage, EngineError> {
+        context.textures.release(self.texture);
+        let device = &context.device;
+        let buffer_slice = self.buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            // The receiver only goes away if `read` has already failed.
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv()
+            .map_err(|_| EngineError::gpu("Readback buffer was dropped before it was mapped"))?
+            .map_err(|e| EngineError::gpu(format!("Failed to map readback buffer: {}", e)))?;
+
+        let unpadded_bytes_per_row = (self.encoding.bytes_per_pixel() * self.size.width) as usize;
+        let data = buffer_slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity(unpadded_bytes_per_row * self.size.height as usize);
+        for row in data.chunks(self.padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row]);
+        }
+        drop(data);
+        self.buffer.unmap();
+
+        Ok(RenderedImage {
+            width: self.size.width,
+            height: self.size.height,
+            encoding: self.encoding,
+            pixels,
+        })
+    }
+}
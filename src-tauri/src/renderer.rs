@@ -0,0 +1,415 @@
+//! The render thread.
+//!
+//! The backend lives on a thread of its own and takes jobs over a channel,
+//! so commands only ever await a reply and waiting on the GPU never blocks
+//! the async runtime. Render jobs are grouped into [`Lane`]s: a new job in a
+//! lane cancels the one before it, which is skipped if it has not started
+//! and stops at the next node if it has.
+
+use crate::backend::cpu::CpuBackend;
+use crate::backend::gpu::GpuBackend;
+use crate::backend::{Backend, BackendKind, ReadbackTarget, RenderMonitor, RenderResult};
+use crate::error::EngineError;
+use crate::graph::Graph;
+use crate::nodes::NodeRegistry;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Renders that supersede each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Lane {
+    /// The graph's output node.
+    Output,
+    /// One output of one node, whatever its encoding.
+    Node { node_id: String, handle: String },
+    /// Thumbnails of every node.
+    Previews,
+}
+
+/// Reported while jobs run, emitted to the frontend as Tauri events named
+/// by [`RenderEvent::name`].
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RenderEvent {
+    #[serde(rename_all = "camelCase")]
+    Progress {
+        job: u64,
+        finished_steps: usize,
+        total_steps: usize,
+    },
+    /// The GPU device was lost and has been set up again, or could not be,
+    /// in which case `error` says why. Everything cached was lost with it.
+    #[serde(rename_all = "camelCase")]
+    DeviceLost {
+        reason: String,
+        error: Option<EngineError>,
+    },
+    /// Sent for every render job, including failed and cancelled ones.
+    #[serde(rename_all = "camelCase")]
+    Finished {
+        job: u64,
+        rendered_nodes: Vec<String>,
+        error: Option<EngineError>,
+    },
+}
+
+impl RenderEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderEvent::Progress { .. } => "render-progress",
+            RenderEvent::DeviceLost { .. } => "device-lost",
+            RenderEvent::Finished { .. } => "render-finished",
+        }
+    }
+}
+
+/// Receives events on the render thread.
+pub type EventSink = Box<dyn Fn(RenderEvent) + Send>;
+
+enum Job {
+    Initialize {
+        kind: BackendKind,
+        reply: oneshot::Sender<Result<String, EngineError>>,
+    },
+    Render {
+        id: u64,
+        graph: Graph,
+        targets: Vec<ReadbackTarget>,
+        cancelled: Arc<AtomicBool>,
+        reply: oneshot::Sender<Result<RenderResult, EngineError>>,
+    },
+}
+
+/// Handle to the render thread, which stops once the handle is dropped.
+pub struct Renderer {
+    jobs: mpsc::Sender<Job>,
+    /// The cancellation flag of the latest job in each lane.
+    lanes: Mutex<HashMap<Lane, Arc<AtomicBool>>>,
+    next_id: AtomicU64,
+}
+
+impl Renderer {
+    pub fn spawn(registry: Arc<NodeRegistry>, events: EventSink) -> Self {
+        let (jobs, receiver) = mpsc::channel();
+        let thread = RenderThread {
+            registry,
+            events,
+            backend: None,
+        };
+        std::thread::Builder::new()
+            .name("render".to_string())
+            .spawn(move || thread.run(receiver))
+            .expect("failed to spawn the render thread");
+
+        Self {
+            jobs,
+            lanes: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Sets up the backend selected by `kind`, replacing the current one and
+    /// everything it has cached.
+    pub async fn initialize(&self, kind: BackendKind) -> Result<String, EngineError> {
+        let (reply, response) = oneshot::channel();
+        self.send(Job::Initialize { kind, reply })?;
+        response.await.map_err(|_| stopped())?
+    }
+
+    /// Queues a render of `targets` in `lane`, cancelling the lane's previous
+    /// job, and waits for it.
+    pub async fn render(
+        &self,
+        lane: Lane,
+        graph: Graph,
+        targets: Vec<ReadbackTarget>,
+    ) -> Result<RenderResult, EngineError> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self.lanes.lock()?.insert(lane.clone(), cancelled.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+
+        let (reply, response) = oneshot::channel();
+        let result = match self.send(Job::Render {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            graph,
+            targets,
+            cancelled: cancelled.clone(),
+            reply,
+        }) {
+            Ok(()) => response
+                .await
+                .map_err(|_| stopped())
+                .and_then(|result| result),
+            Err(e) => Err(e),
+        };
+
+        // Forget the lane unless a newer job has taken it over meanwhile, so
+        // lanes of nodes that are gone do not pile up.
+        let mut lanes = self.lanes.lock()?;
+        if lanes
+            .get(&lane)
+            .is_some_and(|latest| Arc::ptr_eq(latest, &cancelled))
+        {
+            lanes.remove(&lane);
+        }
+        result
+    }
+
+    fn send(&self, job: Job) -> Result<(), EngineError> {
+        self.jobs.send(job).map_err(|_| stopped())
+    }
+}
+
+fn stopped() -> EngineError {
+    EngineError::gpu("The render thread has stopped")
+}
+
+struct RenderThread {
+    registry: Arc<NodeRegistry>,
+    events: EventSink,
+    backend: Option<Box<dyn Backend>>,
+}
+
+impl RenderThread {
+    fn run(mut self, jobs: mpsc::Receiver<Job>) {
+        for job in jobs {
+            match job {
+                Job::Initialize { kind, reply } => {
+                    let _ = reply.send(self.initialize(kind));
+                }
+                Job::Render {
+                    id,
+                    graph,
+                    targets,
+                    cancelled,
+                    reply,
+                } => {
+                    let result = self.render(id, &graph, &targets, &cancelled);
+                    (self.events)(RenderEvent::Finished {
+                        job: id,
+                        rendered_nodes: result
+                            .as_ref()
+                            .map(|r| r.rendered_nodes.clone())
+                            .unwrap_or_default(),
+                        error: result.as_ref().err().cloned(),
+                    });
+                    let _ = reply.send(result);
+                }
+            }
+        }
+    }
+
+    /// `Auto` falls back to the CPU when no GPU adapter or device can be
+    /// had, and says why in the returned description.
+    fn initialize(&mut self, kind: BackendKind) -> Result<String, EngineError> {
+        let (backend, fallback): (Box<dyn Backend>, _) = match kind {
+            BackendKind::Gpu => (Box::new(pollster::block_on(GpuBackend::request())?), None),
+            BackendKind::Cpu => (Box::new(CpuBackend::new()), None),
+            BackendKind::Auto => match pollster::block_on(GpuBackend::request()) {
+                Ok(gpu) => (Box::new(gpu), None),
+                Err(e) => {
+                    eprintln!("{}; falling back to the CPU backend", e);
+                    (Box::new(CpuBackend::new()), Some(e))
+                }
+            },
+        };
+
+        let info = match fallback {
+            Some(e) => format!("Initialized {} ({})", backend.name(), e),
+            None => format!("Initialized {}", backend.name()),
+        };
+        self.backend = Some(backend);
+        Ok(info)
+    }
+
+    /// Renders on the current backend, recovering it first if it lost its
+    /// device. A render that fails because the device was lost meanwhile is
+    /// tried once more on the new one.
+    fn render(
+        &mut self,
+        id: u64,
+        graph: &Graph,
+        targets: &[ReadbackTarget],
+        cancelled: &AtomicBool,
+    ) -> Result<RenderResult, EngineError> {
+        self.recover()?;
+        let result = self.render_once(id, graph, targets, cancelled);
+        if result.is_err() && self.recover()? {
+            return self.render_once(id, graph, targets, cancelled);
+        }
+        result
+    }
+
+    /// Resets a backend that lost its device and tells the frontend.
+    /// Returns whether it did.
+    fn recover(&mut self) -> Result<bool, EngineError> {
+        let Some(backend) = self.backend.as_mut() else {
+            return Ok(false);
+        };
+        let Some(reason) = backend.lost_device() else {
+            return Ok(false);
+        };
+
+        eprintln!("GPU device lost ({}); setting it up again", reason);
+        let result = backend.reset();
+        (self.events)(RenderEvent::DeviceLost {
+            reason,
+            error: result.as_ref().err().cloned(),
+        });
+        result.map(|()| true)
+    }
+
+    fn render_once(
+        &mut self,
+        id: u64,
+        graph: &Graph,
+        targets: &[ReadbackTarget],
+        cancelled: &AtomicBool,
+    ) -> Result<RenderResult, EngineError> {
+        let backend = self
+            .backend
+            .as_mut()
+            .ok_or_else(|| EngineError::gpu("No render backend initialized"))?;
+        let monitor = JobMonitor {
+            id,
+            cancelled,
+            events: &self.events,
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            backend.render(graph, &self.registry, targets, &monitor)
+        }));
+        result.unwrap_or_else(|_| {
+            // Whatever the backend was doing is in an unknown state, so it
+            // has to be set up again.
+            self.backend = None;
+            Err(EngineError::gpu(
+                "The renderer panicked and was shut down; initialize a backend again",
+            ))
+        })
+    }
+}
+
+struct JobMonitor<'a> {
+    id: u64,
+    cancelled: &'a AtomicBool,
+    events: &'a EventSink,
+}
+
+impl RenderMonitor for JobMonitor<'_> {
+    fn step_finished(&self, finished: usize, total: usize) {
+        (self.events)(RenderEvent::Progress {
+            job: self.id,
+            finished_steps: finished,
+            total_steps: total,
+        });
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::graph_from;
+    use crate::graph::{Size, DEFAULT_OUTPUT};
+    use crate::scheduler::OutputKey;
+    use crate::state::OutputEncoding;
+    use serde_json::json;
+
+    /// Two colors mixed into the output, so a render has steps
+    /// left to skip after the first.
+    fn graph() -> Graph {
+        let mut graph = graph_from(
+            &[
+                ("red", "colorNode", json!({})),
+                ("blue", "colorNode", json!({})),
+                ("mix", "mixNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "red", "mix", Some("a")),
+                ("e2", "blue", "mix", Some("b")),
+                ("e3", "mix", "out", None),
+            ],
+        );
+        graph.canvas = Size::new(4, 4);
+        graph
+    }
+
+    fn targets() -> Vec<ReadbackTarget> {
+        vec![ReadbackTarget {
+            key: OutputKey::new("out", DEFAULT_OUTPUT),
+            max_size: None,
+            encoding: OutputEncoding::Rgba8,
+        }]
+    }
+
+    /// A CPU render thread whose events are counted in `progress`, and which
+    /// sets `cancel` once the first step has finished, as a newer job in the
+    /// same lane would.
+    fn thread(progress: Arc<AtomicU64>, cancel: Arc<AtomicBool>) -> RenderThread {
+        RenderThread {
+            registry: Arc::new(NodeRegistry::builtin()),
+            events: Box::new(move |event| {
+                if let RenderEvent::Progress { .. } = event {
+                    progress.fetch_add(1, Ordering::Relaxed);
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            }),
+            backend: Some(Box::new(CpuBackend::new())),
+        }
+    }
+
+    #[test]
+    fn cancelled_job_stops_at_the_next_node() {
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut thread = thread(progress.clone(), cancelled.clone());
+
+        let result = thread.render(1, &graph(), &targets(), &cancelled);
+        assert!(matches!(result, Err(EngineError::Cancelled { .. })));
+        assert_eq!(progress.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn job_cancelled_before_it_starts_runs_nothing() {
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancelled = Arc::new(AtomicBool::new(true));
+        let mut thread = thread(progress.clone(), cancelled.clone());
+
+        let result = thread.render(1, &graph(), &targets(), &cancelled);
+        assert!(matches!(result, Err(EngineError::Cancelled { .. })));
+        assert_eq!(progress.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn job_left_alone_renders() {
+        let progress = Arc::new(AtomicU64::new(0));
+        let mut thread = thread(progress.clone(), Arc::new(AtomicBool::new(false)));
+
+        let result = thread.render(1, &graph(), &targets(), &AtomicBool::new(false));
+        assert_eq!(result.unwrap().rendered_nodes.len(), 4);
+        assert_eq!(progress.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn lane_is_forgotten_once_its_job_finishes() {
+        let renderer = Renderer::spawn(Arc::new(NodeRegistry::builtin()), Box::new(|_| {}));
+        pollster::block_on(async {
+            renderer.initialize(BackendKind::Cpu).await.unwrap();
+            let lane = Lane::Node {
+                node_id: "out".to_string(),
+                handle: DEFAULT_OUTPUT.to_string(),
+            };
+            renderer.render(lane, graph(), targets()).await.unwrap();
+        });
+        assert!(renderer.lanes.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How texels are picked when an input is sampled at a different size.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Filter {
+    /// The texel under the sample point, which keeps pixel art crisp.
+    #[default]
+    Nearest,
+    /// A blend of the four texels around the sample point.
+    Linear,
+}
+
+/// What an input reads outside its edges, which linear filtering reaches
+/// into at the border.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum AddressMode {
+    /// The nearest edge texel.
+    #[default]
+    Clamp,
+    /// The image tiled.
+    Repeat,
+    /// The image tiled, flipping every other tile.
+    Mirror,
+    /// Transparent black.
+    Border,
+}
+
+impl AddressMode {
+    /// Resolves texel `index` along an axis of `extent` texels, or `None`
+    /// if it falls on the border.
+    pub fn resolve(self, index: i64, extent: u32) -> Option<u32> {
+        let extent = i64::from(extent);
+        let index = match self {
+            AddressMode::Clamp => index.clamp(0, extent - 1),
+            AddressMode::Repeat => index.rem_euclid(extent),
+            AddressMode::Mirror => {
+                let index = index.rem_euclid(2 * extent);
+                if index < extent {
+                    index
+                } else {
+                    2 * extent - 1 - index
+                }
+            }
+            AddressMode::Border if (0..extent).contains(&index) => index,
+            AddressMode::Border => return None,
+        };
+        Some(index as u32)
+    }
+
+    fn wgpu(self) -> wgpu::AddressMode {
+        match self {
+            AddressMode::Clamp => wgpu::AddressMode::ClampToEdge,
+            AddressMode::Repeat => wgpu::AddressMode::Repeat,
+            AddressMode::Mirror => wgpu::AddressMode::MirrorRepeat,
+            // Emulated by `sample_input` in the shaders.
+            AddressMode::Border => wgpu::AddressMode::ClampToEdge,
+        }
+    }
+}
+
+/// How a node samples one of its inputs, set per input in the node's
+/// `data.samplers`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct SamplerSettings {
+    #[serde(default)]
+    pub filter: Filter,
+    #[serde(default)]
+    pub address: AddressMode,
+}
+
+impl SamplerSettings {
+    /// The `Sampling` uniform the shaders' `sample_input` takes alongside
+    /// the sampler.
+    pub fn sampling(self) -> [u32; 4] {
+        [
+            (self.address == AddressMode::Border).into(),
+            (self.filter == Filter::Linear).into(),
+            0,
+            0,
+        ]
+    }
+}
+
+/// Creates a sampler the first time a combination of settings is asked for
+/// and keeps it for the lifetime of the device.
+#[derive(Default)]
+pub struct SamplerCache {
+    samplers: RefCell<HashMap<SamplerSettings, Arc<wgpu::Sampler>>>,
+}
+
+impl SamplerCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the sampler for `settings`.
+    pub fn get(&self, device: &wgpu::Device, settings: SamplerSettings) -> Arc<wgpu::Sampler> {
+        if let Some(sampler) = self.samplers.borrow().get(&settings) {
+            return sampler.clone();
+        }
+
+        let address_mode = settings.address.wgpu();
+        let filter = match settings.filter {
+            Filter::Nearest => wgpu::FilterMode::Nearest,
+            Filter::Linear => wgpu::FilterMode::Linear,
+        };
+        let sampler = Arc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Input Sampler"),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }));
+        self.samplers.borrow_mut().insert(settings, sampler.clone());
+        sampler
+    }
+}
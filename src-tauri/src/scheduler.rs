@@ -0,0 +1,218 @@
+use crate::error::EngineError;
+use crate::graph::{Graph, Node};
+use std::collections::{HashMap, HashSet};
+
+/// Identifies one output of one node in the per-render output table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OutputKey {
+    pub node_id: String,
+    pub handle: String,
+}
+
+impl OutputKey {
+    pub fn new(node_id: &str, handle: &str) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            handle: handle.to_string(),
+        }
+    }
+}
+
+/// A node to run, with the upstream output wired into each of its inputs.
+pub struct Step<'a> {
+    pub node: &'a Node,
+    pub inputs: HashMap<String, OutputKey>,
+}
+
+impl Step<'_> {
+    pub fn input(&self, handle: &str) -> Option<&OutputKey> {
+        self.inputs.get(handle)
+    }
+}
+
+/// The nodes needed to produce a set of target nodes, in dependency order, so
+/// each node runs exactly once and only after everything it reads from.
+pub struct Schedule<'a> {
+    pub steps: Vec<Step<'a>>,
+}
+
+impl<'a> Schedule<'a> {
+    /// Builds the schedule for `target_ids` and the nodes upstream of them.
+    /// Nodes shared between targets are scheduled once.
+    ///
+    /// Expects a graph that passed [`Graph::validate`]; a cycle is still
+    /// reported as an error rather than looping.
+    pub fn build(graph: &'a Graph, target_ids: &[&str]) -> Result<Self, EngineError> {
+        let mut incoming: HashMap<&str, Vec<&crate::graph::Edge>> = HashMap::new();
+        for edge in &graph.edges {
+            incoming.entry(edge.target.as_str()).or_default().push(edge);
+        }
+
+        // Collect everything the target depends on.
+        let mut needed: HashSet<&str> = HashSet::new();
+        let mut pending = target_ids.to_vec();
+        while let Some(id) = pending.pop() {
+            if !needed.insert(id) {
+                continue;
+            }
+            if graph.get_node(id).is_none() {
+                return Err(EngineError::invalid(format!("Node not found: {}", id)));
+            }
+            for edge in incoming.get(id).into_iter().flatten() {
+                pending.push(edge.source.as_str());
+            }
+        }
+
+        // Kahn's algorithm over the needed subgraph, seeded in graph order so
+        // the schedule is stable between renders.
+        let mut remaining: HashMap<&str, usize> = needed
+            .iter()
+            .map(|&id| (id, incoming.get(id).map_or(0, Vec::len)))
+            .collect();
+        let mut outgoing: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &graph.edges {
+            if needed.contains(edge.target.as_str()) {
+                outgoing
+                    .entry(edge.source.as_str())
+                    .or_default()
+                    .push(edge.target.as_str());
+            }
+        }
+
+        let mut ready: Vec<&Node> = graph
+            .nodes
+            .iter()
+            .filter(|n| remaining.get(n.id.as_str()) == Some(&0))
+            .rev()
+            .collect();
+        let mut steps = Vec::with_capacity(needed.len());
+
+        while let Some(node) = ready.pop() {
+            let inputs = incoming
+                .get(node.id.as_str())
+                .into_iter()
+                .flatten()
+                .map(|e| {
+                    (
+                        e.input_handle().to_string(),
+                        OutputKey::new(&e.source, e.output_handle()),
+                    )
+                })
+                .collect();
+            steps.push(Step { node, inputs });
+
+            for &target in outgoing.get(node.id.as_str()).into_iter().flatten() {
+                let count = remaining
+                    .get_mut(target)
+                    .expect("edge targets are in the needed set");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(graph.get_node(target).expect("needed nodes exist"));
+                }
+            }
+        }
+
+        if steps.len() != needed.len() {
+            return Err(EngineError::invalid(format!(
+                "Graph has a cycle upstream of node(s) {}",
+                target_ids.join(", ")
+            )));
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::graph_from;
+    use serde_json::json;
+
+    fn order(graph: &Graph, target: &str) -> Vec<String> {
+        Schedule::build(graph, &[target])
+            .unwrap()
+            .steps
+            .iter()
+            .map(|step| step.node.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn leaves_out_nodes_the_target_does_not_need() {
+        let graph = graph_from(
+            &[
+                ("c1", "colorNode", json!({})),
+                ("c2", "colorNode", json!({})),
+                ("m1", "mixNode", json!({})),
+                ("c3", "colorNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "c1", "m1", Some("a")),
+                ("e2", "c2", "m1", Some("b")),
+                ("e3", "m1", "out", None),
+            ],
+        );
+        assert_eq!(order(&graph, "out"), ["c1", "c2", "m1", "out"]);
+        assert_eq!(order(&graph, "c3"), ["c3"]);
+    }
+
+    #[test]
+    fn order_follows_graph_order_and_is_stable() {
+        let graph = graph_from(
+            &[
+                ("out", "outputNode", json!({})),
+                ("m1", "mixNode", json!({})),
+                ("c2", "colorNode", json!({})),
+                ("c1", "colorNode", json!({})),
+            ],
+            &[
+                ("e1", "c1", "m1", Some("a")),
+                ("e2", "c2", "m1", Some("b")),
+                ("e3", "m1", "out", None),
+            ],
+        );
+        let first = order(&graph, "out");
+        assert_eq!(first, ["c2", "c1", "m1", "out"]);
+        assert_eq!(order(&graph, "out"), first);
+    }
+
+    #[test]
+    fn shared_nodes_are_scheduled_once() {
+        let graph = graph_from(
+            &[
+                ("c1", "colorNode", json!({})),
+                ("m1", "mixNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "c1", "m1", Some("a")),
+                ("e2", "c1", "m1", Some("b")),
+                ("e3", "m1", "out", None),
+            ],
+        );
+        let schedule = Schedule::build(&graph, &["out"]).unwrap();
+        assert_eq!(schedule.steps.len(), 3);
+        let mix = &schedule.steps[1];
+        assert_eq!(mix.input("a"), Some(&OutputKey::new("c1", "out")));
+        assert_eq!(mix.input("b"), Some(&OutputKey::new("c1", "out")));
+    }
+
+    #[test]
+    fn cycle_is_an_error() {
+        let graph = graph_from(
+            &[
+                ("m1", "mixNode", json!({})),
+                ("m2", "mixNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "m1", "m2", Some("a")),
+                ("e2", "m2", "m1", Some("a")),
+                ("e3", "m2", "out", None),
+            ],
+        );
+        assert!(Schedule::build(&graph, &["out"]).is_err());
+    }
+}
@@ -1,16 +1,117 @@
-pub const MIX_SHADER: &str = r#"
-struct MixUniforms {
-    factor: f32,
-    p1: f32,
-    p2: f32,
-    p3: f32,
-};
+/// A WGSL shader together with the bind group layout its pipelines use.
+///
+/// Fragment shaders only contain their `@fragment` entry points; the
+/// full-screen vertex stage from [`FULLSCREEN_VERTEX`] and the input
+/// sampling helper from [`SAMPLE_INPUT`] are prepended when the pipeline is
+/// built.
+pub struct ShaderDef {
+    pub id: &'static str,
+    pub source: &'static str,
+    pub bindings: &'static [wgpu::BindGroupLayoutEntry],
+}
 
-@group(0) @binding(0) var tex_a: texture_2d<f32>;
-@group(0) @binding(1) var tex_b: texture_2d<f32>;
-@group(0) @binding(2) var samp: sampler;
-@group(0) @binding(3) var<uniform> uniforms: MixUniforms;
+/// A WGSL compute shader for nodes that write their output as a storage
+/// texture.
+///
+/// The source is prefixed with [`COMPUTE_PRELUDE`] for the working format
+/// when the pipeline is built, which declares the output texture at binding
+/// 0. `bindings` lists the shader's own bindings, which start at 1.
+pub struct ComputeShaderDef {
+    pub id: &'static str,
+    pub source: &'static str,
+    pub bindings: &'static [wgpu::BindGroupLayoutEntry],
+}
+
+/// Declarations shared by every compute shader. `{format}` is replaced with
+/// the WGSL name of the output texture's format.
+///
+/// Entry points should use `@workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)`
+/// and return early for invocations outside `textureDimensions(output)`, as
+/// the last workgroups of a row or column overhang the texture.
+pub const COMPUTE_PRELUDE: &str = r#"
+const WORKGROUP_SIZE: u32 = 8u;
+
+@group(0) @binding(0) var output: texture_storage_2d<{format}, write>;
+"#;
+
+/// The edge length of the square workgroups compute shaders declare.
+pub const WORKGROUP_SIZE: u32 = 8;
+
+const fn compute_texture(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        },
+        count: None,
+    }
+}
+
+const fn compute_uniform(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+const fn compute_storage(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+const fn fragment_texture(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    }
+}
 
+const fn fragment_sampler(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    }
+}
+
+const fn fragment_uniform(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+pub const FULLSCREEN_VERTEX: &str = r#"
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
@@ -30,18 +131,681 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
         vec2<f32>(2.0, 0.0),
         vec2<f32>(0.0, 2.0)
     );
-    
+
     let pos = positions[in_vertex_index];
     out.position = vec4<f32>(pos, 0.0, 1.0);
     out.uv = uvs[in_vertex_index];
     return out;
 }
+"#;
+
+/// Samples node inputs with their sampler settings. Samplers cannot clamp
+/// to a transparent border on every adapter, so `sample_input` emulates it
+/// on a clamp-to-edge sampler: only the share of the filter's taps that lie
+/// inside the input is kept, which is what a border sampler blends in.
+pub const SAMPLE_INPUT: &str = r#"
+// Mirrors `SamplerSettings::sampling` in samplers.rs.
+struct Sampling {
+    border: u32,
+    linear: u32,
+    p0: u32,
+    p1: u32,
+};
+
+fn sample_input(tex: texture_2d<f32>, samp: sampler, sampling: Sampling, uv: vec2<f32>) -> vec4<f32> {
+    let color = textureSample(tex, samp, uv);
+    if (sampling.border == 0u) {
+        return color;
+    }
+
+    let size = vec2<f32>(textureDimensions(tex));
+    var inside: vec2<f32>;
+    if (sampling.linear == 1u) {
+        let texel = uv * size - 0.5;
+        let base = floor(texel);
+        let weight = texel - base;
+        let next = base + 1.0;
+        inside = select(vec2<f32>(0.0), 1.0 - weight, base >= vec2<f32>(0.0) & base < size)
+            + select(vec2<f32>(0.0), weight, next >= vec2<f32>(0.0) & next < size);
+    } else {
+        let texel = floor(uv * size);
+        inside = select(vec2<f32>(0.0), vec2<f32>(1.0), texel >= vec2<f32>(0.0) & texel < size);
+    }
+    return color * inside.x * inside.y;
+}
+"#;
+
+pub const MIX_SHADER: &str = r#"
+struct MixUniforms {
+    factor: f32,
+    p1: f32,
+    p2: f32,
+    p3: f32,
+    sampling_a: Sampling,
+    sampling_b: Sampling,
+};
+
+@group(0) @binding(0) var tex_a: texture_2d<f32>;
+@group(0) @binding(1) var tex_b: texture_2d<f32>;
+@group(0) @binding(2) var samp_a: sampler;
+@group(0) @binding(3) var samp_b: sampler;
+@group(0) @binding(4) var<uniform> uniforms: MixUniforms;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color_a = textureSample(tex_a, samp, in.uv);
-    let color_b = textureSample(tex_b, samp, in.uv);
-    
+    let color_a = sample_input(tex_a, samp_a, uniforms.sampling_a, in.uv);
+    let color_b = sample_input(tex_b, samp_b, uniforms.sampling_b, in.uv);
+
     return mix(color_a, color_b, uniforms.factor);
 }
 "#;
+
+pub const MIX: ShaderDef = ShaderDef {
+    id: "mix",
+    source: MIX_SHADER,
+    bindings: &[
+        // Texture A
+        fragment_texture(0),
+        // Texture B
+        fragment_texture(1),
+        // Sampler A
+        fragment_sampler(2),
+        // Sampler B
+        fragment_sampler(3),
+        // Uniforms (Factor, Sampling A and B)
+        fragment_uniform(4),
+    ],
+};
+
+pub const SPLIT_SHADER: &str = r#"
+@group(0) @binding(0) var tex: texture_2d<f32>;
+@group(0) @binding(1) var samp: sampler;
+@group(0) @binding(2) var<uniform> sampling: Sampling;
+
+fn gray(value: f32) -> vec4<f32> {
+    return vec4<f32>(value, value, value, 1.0);
+}
+
+@fragment
+fn fs_r(in: VertexOutput) -> @location(0) vec4<f32> {
+    return gray(sample_input(tex, samp, sampling, in.uv).r);
+}
+
+@fragment
+fn fs_g(in: VertexOutput) -> @location(0) vec4<f32> {
+    return gray(sample_input(tex, samp, sampling, in.uv).g);
+}
+
+@fragment
+fn fs_b(in: VertexOutput) -> @location(0) vec4<f32> {
+    return gray(sample_input(tex, samp, sampling, in.uv).b);
+}
+
+@fragment
+fn fs_a(in: VertexOutput) -> @location(0) vec4<f32> {
+    return gray(sample_input(tex, samp, sampling, in.uv).a);
+}
+"#;
+
+pub const SPLIT: ShaderDef = ShaderDef {
+    id: "split",
+    source: SPLIT_SHADER,
+    bindings: &[
+        // Source texture
+        fragment_texture(0),
+        // Sampler
+        fragment_sampler(1),
+        // Sampling
+        fragment_uniform(2),
+    ],
+};
+
+pub const OUTPUT_SHADER: &str = r#"
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var samp: sampler;
+
+// Copies as is. Format conversion, including sRGB encoding, happens when the
+// result is written to the target.
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source, samp, in.uv);
+}
+
+// Rec. 709 luma, for single-channel targets.
+@fragment
+fn fs_gray(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, samp, in.uv);
+    let luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return vec4<f32>(luma, luma, luma, color.a);
+}
+"#;
+
+pub const OUTPUT: ShaderDef = ShaderDef {
+    id: "output",
+    source: OUTPUT_SHADER,
+    bindings: &[
+        // Source texture
+        fragment_texture(0),
+        // Sampler
+        fragment_sampler(1),
+    ],
+};
+
+pub const DILATE_SHADER: &str = r#"
+@group(0) @binding(1) var source: texture_2d<f32>;
+
+// The source texel under output pixel `coord`, which is clamped to the
+// output first. The source can be a different size on the first pass.
+fn fetch(coord: vec2<i32>) -> vec4<f32> {
+    let out_size = vec2<i32>(textureDimensions(output));
+    let in_size = vec2<i32>(textureDimensions(source));
+    let pixel = clamp(coord, vec2<i32>(0), out_size - 1);
+    let uv = (vec2<f32>(pixel) + 0.5) / vec2<f32>(out_size);
+    let texel = clamp(vec2<i32>(floor(uv * vec2<f32>(in_size))), vec2<i32>(0), in_size - 1);
+    return textureLoad(source, texel, 0);
+}
+
+// One pixel of growth: the channel-wise maximum of a pixel and its four
+// direct neighbours.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let coord = vec2<i32>(id.xy);
+    var color = fetch(coord);
+    color = max(color, fetch(coord + vec2<i32>(1, 0)));
+    color = max(color, fetch(coord - vec2<i32>(1, 0)));
+    color = max(color, fetch(coord + vec2<i32>(0, 1)));
+    color = max(color, fetch(coord - vec2<i32>(0, 1)));
+    textureStore(output, coord, color);
+}
+
+// No growth: resamples the source to the output size, for a zero radius.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_copy(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let coord = vec2<i32>(id.xy);
+    textureStore(output, coord, fetch(coord));
+}
+"#;
+
+pub const DILATE: ComputeShaderDef = ComputeShaderDef {
+    id: "dilate",
+    source: DILATE_SHADER,
+    bindings: &[
+        // Previous pass
+        compute_texture(1),
+    ],
+};
+
+pub const NOISE_SHADER: &str = r#"
+struct NoiseUniforms {
+    kind: u32,
+    seed: u32,
+    octaves: u32,
+    tileable: u32,
+    frequencies: array<vec4<f32>, 2>,
+    weights: array<vec4<f32>, 2>,
+}
+
+@group(0) @binding(1) var<uniform> params: NoiseUniforms;
+
+const VALUE: u32 = 0u;
+const PERLIN: u32 = 1u;
+const SIMPLEX: u32 = 2u;
+
+const GOLDEN: u32 = 0x9e3779b9u;
+const SIMPLEX_SKEW: f32 = 0.3660254;
+const SIMPLEX_UNSKEW: f32 = 0.21132487;
+const SIMPLEX_SKEW_4D: f32 = 0.309017;
+const SIMPLEX_UNSKEW_4D: f32 = 0.1381966;
+const TAU: f32 = 6.2831855;
+
+// The PCG hash (Jarzynski and Olano, 2020).
+fn hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// The top 24 bits of `h` in [0, 1), which f32 holds exactly.
+fn unit(h: u32) -> f32 {
+    return f32(h >> 8u) / 16777216.0;
+}
+
+// One of eight gradient directions, picked by the low bits of `h`.
+fn gradient(h: u32) -> vec2<f32> {
+    let g = h & 7u;
+    if (g < 4u) {
+        return vec2<f32>(select(1.0, -1.0, (g & 1u) != 0u), select(1.0, -1.0, (g & 2u) != 0u));
+    }
+    let sign = select(1.0, -1.0, (g & 1u) != 0u);
+    return select(vec2<f32>(0.0, sign), vec2<f32>(sign, 0.0), g < 6u);
+}
+
+// One of the 32 edge midpoints of the 4D hypercube: bits 3 and 4 of `h`
+// choose the zero axis, bits 0 to 2 the signs of the other three.
+fn gradient_4d(h: u32) -> vec4<f32> {
+    let zero = (h >> 3u) & 3u;
+    var g = vec4<f32>(0.0);
+    var bit = 0u;
+    for (var axis = 0u; axis < 4u; axis++) {
+        if (axis != zero) {
+            g[axis] = select(1.0, -1.0, ((h >> bit) & 1u) != 0u);
+            bit++;
+        }
+    }
+    return g;
+}
+
+fn fade(t: vec2<f32>) -> vec2<f32> {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+// The lattice of one octave: its seed and, when tiling, the number of cells
+// after which it repeats.
+struct Lattice {
+    seed: u32,
+    period: i32,
+}
+
+fn hash_cell(lattice: Lattice, cell: vec2<i32>) -> u32 {
+    var c = cell;
+    if (params.tileable != 0u) {
+        c = ((c % lattice.period) + lattice.period) % lattice.period;
+    }
+    return hash(bitcast<u32>(c.x) ^ hash(bitcast<u32>(c.y) ^ lattice.seed));
+}
+
+fn value_noise(lattice: Lattice, p: vec2<f32>) -> f32 {
+    let cell = vec2<i32>(floor(p));
+    let u = fade(p - floor(p));
+    let a = unit(hash_cell(lattice, cell));
+    let b = unit(hash_cell(lattice, cell + vec2<i32>(1, 0)));
+    let c = unit(hash_cell(lattice, cell + vec2<i32>(0, 1)));
+    let d = unit(hash_cell(lattice, cell + vec2<i32>(1, 1)));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+fn perlin_corner(lattice: Lattice, cell: vec2<i32>, f: vec2<f32>, offset: vec2<i32>) -> f32 {
+    let g = gradient(hash_cell(lattice, cell + offset));
+    return dot(g, f - vec2<f32>(offset));
+}
+
+fn perlin_noise(lattice: Lattice, p: vec2<f32>) -> f32 {
+    let cell = vec2<i32>(floor(p));
+    let f = p - floor(p);
+    let u = fade(f);
+    let a = perlin_corner(lattice, cell, f, vec2<i32>(0, 0));
+    let b = perlin_corner(lattice, cell, f, vec2<i32>(1, 0));
+    let c = perlin_corner(lattice, cell, f, vec2<i32>(0, 1));
+    let d = perlin_corner(lattice, cell, f, vec2<i32>(1, 1));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+fn simplex_corner(lattice: Lattice, cell: vec2<i32>, d: vec2<f32>) -> f32 {
+    let t = 0.5 - dot(d, d);
+    if (t <= 0.0) {
+        return 0.0;
+    }
+    return t * t * t * t * dot(gradient(hash_cell(lattice, cell)), d);
+}
+
+// Gustavson's 4D simplex noise in about [-1, 1]. Lattice points are never
+// wrapped; the torus in `simplex_noise` is what repeats.
+fn simplex_noise_4d(lattice: Lattice, p: vec4<f32>) -> f32 {
+    let s = dot(p, vec4<f32>(1.0)) * SIMPLEX_SKEW_4D;
+    let cell = floor(p + s);
+    let t = dot(cell, vec4<f32>(1.0)) * SIMPLEX_UNSKEW_4D;
+    let d0 = p - (cell - t);
+
+    // Each axis's rank among the offsets decides at which of the four steps
+    // through the simplex it is taken.
+    var rank = vec4<u32>(0u);
+    for (var i = 0; i < 4; i++) {
+        for (var j = i + 1; j < 4; j++) {
+            if (d0[i] > d0[j]) {
+                rank[i] += 1u;
+            } else {
+                rank[j] += 1u;
+            }
+        }
+    }
+
+    let base = vec4<i32>(cell);
+    var sum = 0.0;
+    for (var n = 0u; n <= 4u; n++) {
+        let offset = vec4<u32>(rank >= vec4<u32>(4u - n));
+        let d = d0 - vec4<f32>(offset) + f32(n) * SIMPLEX_UNSKEW_4D;
+        let falloff = 0.6 - dot(d, d);
+        if (falloff > 0.0) {
+            let c = bitcast<vec4<u32>>(base + vec4<i32>(offset));
+            let g = gradient_4d(hash(c.x ^ hash(c.y ^ hash(c.z ^ hash(c.w ^ lattice.seed)))));
+            sum += falloff * falloff * falloff * falloff * dot(g, d);
+        }
+    }
+    return 27.0 * sum;
+}
+
+// Simplex noise in about [-1, 1]. The skewed lattice only repeats along its
+// own axes, never along both of the canvas's, so a tiling lattice is sampled
+// on a torus in 4D instead, each axis going once around a circle whose
+// circumference is the period.
+fn simplex_noise(lattice: Lattice, p: vec2<f32>) -> f32 {
+    if (params.tileable != 0u) {
+        let period = f32(lattice.period);
+        let angle = p / period * TAU;
+        let radius = period / TAU;
+        return simplex_noise_4d(
+            lattice,
+            radius * vec4<f32>(cos(angle.x), sin(angle.x), cos(angle.y), sin(angle.y)),
+        );
+    }
+
+    let s = (p.x + p.y) * SIMPLEX_SKEW;
+    let cell = floor(p + s);
+    let t = (cell.x + cell.y) * SIMPLEX_UNSKEW;
+    let d0 = p - (cell - t);
+    let step = select(vec2<i32>(0, 1), vec2<i32>(1, 0), d0.x > d0.y);
+    let d1 = d0 - vec2<f32>(step) + SIMPLEX_UNSKEW;
+    let d2 = d0 - 1.0 + 2.0 * SIMPLEX_UNSKEW;
+
+    let base = vec2<i32>(cell);
+    return 70.0 * (simplex_corner(lattice, base, d0)
+        + simplex_corner(lattice, base + step, d1)
+        + simplex_corner(lattice, base + vec2<i32>(1, 1), d2));
+}
+
+fn worley_noise(lattice: Lattice, p: vec2<f32>) -> f32 {
+    let cell = vec2<i32>(floor(p));
+    var nearest = 3.4028235e38;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let neighbour = cell + vec2<i32>(dx, dy);
+            let h = hash_cell(lattice, neighbour);
+            let point = vec2<f32>(neighbour) + vec2<f32>(unit(h), unit(hash(h)));
+            let d = point - p;
+            nearest = min(nearest, dot(d, d));
+        }
+    }
+    return sqrt(nearest);
+}
+
+// The noise at lattice coordinates `p`, in [0, 1].
+fn noise(lattice: Lattice, p: vec2<f32>) -> f32 {
+    switch params.kind {
+        case VALUE: {
+            return value_noise(lattice, p);
+        }
+        case PERLIN: {
+            return clamp(0.5 + 0.5 * perlin_noise(lattice, p), 0.0, 1.0);
+        }
+        case SIMPLEX: {
+            return clamp(0.5 + 0.5 * simplex_noise(lattice, p), 0.0, 1.0);
+        }
+        default: {
+            return clamp(worley_noise(lattice, p), 0.0, 1.0);
+        }
+    }
+}
+
+// Sums the octaves at the pixel's center. Frequencies and weights are worked
+// out on the CPU.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if (any(id.xy >= size)) {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+
+    var value = 0.0;
+    for (var i = 0u; i < params.octaves; i++) {
+        let frequency = params.frequencies[i / 4u][i % 4u];
+        let lattice = Lattice(hash(params.seed + i * GOLDEN), i32(frequency));
+        value += params.weights[i / 4u][i % 4u] * noise(lattice, uv * frequency);
+    }
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(value, value, value, 1.0));
+}
+"#;
+
+pub const NOISE: ComputeShaderDef = ComputeShaderDef {
+    id: "noise",
+    source: NOISE_SHADER,
+    bindings: &[
+        // Noise uniforms
+        compute_uniform(1),
+    ],
+};
+
+pub const SHAPE_SHADER: &str = r#"
+@group(0) @binding(1) var coverage: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> color: vec4<f32>;
+
+// Whether the pixel is part of the shape.
+fn covered(coord: vec2<i32>) -> bool {
+    return textureLoad(coverage, coord, 0).r > 0.5;
+}
+
+// The shape's color on transparent black.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_color(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let coord = vec2<i32>(id.xy);
+    textureStore(output, coord, select(vec4<f32>(0.0), color, covered(coord)));
+}
+
+// White where the shape is, black elsewhere.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_mask(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let coord = vec2<i32>(id.xy);
+    let value = select(0.0, 1.0, covered(coord));
+    textureStore(output, coord, vec4<f32>(value, value, value, 1.0));
+}
+"#;
+
+pub const SHAPE: ComputeShaderDef = ComputeShaderDef {
+    id: "shape",
+    source: SHAPE_SHADER,
+    bindings: &[
+        // Coverage
+        compute_texture(1),
+        // Color
+        compute_uniform(2),
+    ],
+};
+
+pub const PATTERN_SHADER: &str = r#"
+struct PatternUniforms {
+    color: vec4<f32>,
+    background: vec4<f32>,
+    offset: vec2<i32>,
+}
+
+@group(0) @binding(1) var tile: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> params: PatternUniforms;
+
+// Whether the pixel is colored, repeating the tile from the offset, which
+// is already wrapped into the tile so the remainder is never negative.
+fn covered(coord: vec2<i32>) -> bool {
+    let size = vec2<i32>(textureDimensions(tile));
+    return textureLoad(tile, (coord + size - params.offset) % size, 0).r > 0.5;
+}
+
+// The color where the pattern is, the background elsewhere.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_color(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let coord = vec2<i32>(id.xy);
+    textureStore(output, coord, select(params.background, params.color, covered(coord)));
+}
+
+// White where the pattern is, black elsewhere.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_mask(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let coord = vec2<i32>(id.xy);
+    let value = select(0.0, 1.0, covered(coord));
+    textureStore(output, coord, vec4<f32>(value, value, value, 1.0));
+}
+"#;
+
+pub const PATTERN: ComputeShaderDef = ComputeShaderDef {
+    id: "pattern",
+    source: PATTERN_SHADER,
+    bindings: &[
+        // Tile
+        compute_texture(1),
+        // Colors and offset
+        compute_uniform(2),
+    ],
+};
+
+pub const GRADIENT_SHADER: &str = r#"
+struct GradientUniforms {
+    kind: u32,
+    banding: u32,
+    bands: u32,
+    start: vec2<f32>,
+    end: vec2<f32>,
+}
+
+struct Stop {
+    color: vec4<f32>,
+    position: f32,
+}
+
+@group(0) @binding(1) var<uniform> params: GradientUniforms;
+// Sorted by position, at least one.
+@group(0) @binding(2) var<storage, read> stops: array<Stop>;
+
+const LINEAR: u32 = 0u;
+const RADIAL: u32 = 1u;
+const ANGULAR: u32 = 2u;
+
+const SMOOTH: u32 = 0u;
+const BANDS: u32 = 1u;
+
+const TAU: f32 = 6.2831855;
+
+fn cross2(a: vec2<f32>, b: vec2<f32>) -> f32 {
+    return a.x * b.y - a.y * b.x;
+}
+
+// How far along the gradient the pixel centered at `p` is, in [0, 1].
+fn gradient_position(p: vec2<f32>) -> f32 {
+    let d = params.end - params.start;
+    let q = p - params.start;
+    let length2 = dot(d, d);
+    if (length2 == 0.0) {
+        return 0.0;
+    }
+    var t: f32;
+    switch params.kind {
+        case LINEAR: {
+            t = dot(q, d) / length2;
+        }
+        case RADIAL: {
+            t = sqrt(dot(q, q) / length2);
+        }
+        case ANGULAR: {
+            t = atan2(cross2(d, q), dot(d, q)) / TAU;
+            if (t < 0.0) {
+                t += 1.0;
+            }
+        }
+        default: {
+            t = (abs(dot(q, d)) + abs(cross2(d, q))) / length2;
+        }
+    }
+    return clamp(t, 0.0, 1.0);
+}
+
+// The first stop past `t`, or the number of stops if there is none.
+fn next_stop(t: f32) -> u32 {
+    let count = arrayLength(&stops);
+    for (var i = 0u; i < count; i++) {
+        if (stops[i].position > t) {
+            return i;
+        }
+    }
+    return count;
+}
+
+// How far `t` is from stop `i - 1` towards stop `i`.
+fn stop_fraction(i: u32, t: f32) -> f32 {
+    let a = stops[i - 1u].position;
+    return (t - a) / (stops[i].position - a);
+}
+
+// The color of a smooth ramp at `t`.
+fn ramp(t: f32) -> vec4<f32> {
+    let i = next_stop(t);
+    if (i == 0u) {
+        return stops[0].color;
+    }
+    if (i == arrayLength(&stops)) {
+        return stops[i - 1u].color;
+    }
+    return mix(stops[i - 1u].color, stops[i].color, stop_fraction(i, t));
+}
+
+// Only the stop colors, picked with a 4x4 ordered dither.
+fn dither(t: f32, coord: vec2<u32>) -> vec4<f32> {
+    let i = next_stop(t);
+    if (i == 0u) {
+        return stops[0].color;
+    }
+    if (i == arrayLength(&stops)) {
+        return stops[i - 1u].color;
+    }
+    var bayer = array<u32, 16>(0u, 8u, 2u, 10u, 12u, 4u, 14u, 6u, 3u, 11u, 1u, 9u, 15u, 7u, 13u, 5u);
+    let threshold = (f32(bayer[(coord.y % 4u) * 4u + coord.x % 4u]) + 0.5) / 16.0;
+    return select(stops[i - 1u].color, stops[i].color, stop_fraction(i, t) > threshold);
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    let t = gradient_position(vec2<f32>(id.xy) + 0.5);
+    var color: vec4<f32>;
+    switch params.banding {
+        case SMOOTH: {
+            color = ramp(t);
+        }
+        case BANDS: {
+            let band = min(u32(t * f32(params.bands)), params.bands - 1u);
+            color = ramp(f32(band) / f32(params.bands - 1u));
+        }
+        default: {
+            color = dither(t, id.xy);
+        }
+    }
+    textureStore(output, vec2<i32>(id.xy), color);
+}
+"#;
+
+pub const GRADIENT: ComputeShaderDef = ComputeShaderDef {
+    id: "gradient",
+    source: GRADIENT_SHADER,
+    bindings: &[
+        // Gradient uniforms
+        compute_uniform(1),
+        // Color stops
+        compute_storage(2),
+    ],
+};
@@ -1,5 +1,16 @@
-use std::sync::Mutex;
-use wgpu::util::DeviceExt;
+use crate::backend::{BackendKind, ReadbackTarget};
+use crate::error::EngineError;
+use crate::graph::{Graph, Node, NodeKind, DEFAULT_OUTPUT};
+use crate::nodes::{NodeProcessor, NodeRegistry};
+use crate::pipelines::PipelineCache;
+use crate::renderer::{EventSink, Lane, Renderer};
+use crate::samplers::SamplerCache;
+use crate::scheduler::OutputKey;
+use crate::stats::RenderStats;
+use crate::textures::TexturePool;
+use crate::validation::Diagnostic;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 use wgpu::{Adapter, Device, Instance, Queue};
 
 pub struct RenderContext {
@@ -7,519 +18,327 @@ pub struct RenderContext {
     pub adapter: Adapter,
     pub device: Device,
     pub queue: Queue,
-    pub mix_pipeline: wgpu::RenderPipeline,
-    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub pipelines: PipelineCache,
+    pub samplers: SamplerCache,
+    pub textures: TexturePool,
+}
+
+/// How the pixels of a [`RenderedImage`] are encoded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputEncoding {
+    /// Four bytes per pixel, the working values as they are, e.g. linear
+    /// light for data textures.
+    Rgba8,
+    /// Four bytes per pixel with the color channels sRGB-encoded, ready for
+    /// display. Gamma-space graphs already work in sRGB and are copied as is.
+    #[default]
+    Srgb8,
+    /// One byte per pixel, the Rec. 709 luma of the working values.
+    Gray8,
+    /// Eight bytes per pixel, four little-endian half floats of the working
+    /// values, for export without 8-bit quantization.
+    Rgba16f,
+}
+
+impl OutputEncoding {
+    /// The names the encodings are serialized as, for parameter schemas.
+    pub const NAMES: &'static [&'static str] = &["rgba8", "srgb8", "gray8", "rgba16f"];
+
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            OutputEncoding::Rgba8 | OutputEncoding::Srgb8 => 4,
+            OutputEncoding::Gray8 => 1,
+            OutputEncoding::Rgba16f => 8,
+        }
+    }
+}
+
+/// Tightly packed pixels, row by row, with their dimensions and encoding.
+#[derive(Serialize, Debug, Clone)]
+pub struct RenderedImage {
+    pub width: u32,
+    pub height: u32,
+    pub encoding: OutputEncoding,
+    pub pixels: Vec<u8>,
+}
+
+impl RenderedImage {
+    /// Expands the pixels to RGBA8, e.g. to save or display them. Half
+    /// floats are clamped to `0.0..=1.0`.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        match self.encoding {
+            OutputEncoding::Rgba8 | OutputEncoding::Srgb8 => self.pixels.clone(),
+            OutputEncoding::Gray8 => self
+                .pixels
+                .iter()
+                .flat_map(|&luma| [luma, luma, luma, 255])
+                .collect(),
+            OutputEncoding::Rgba16f => self
+                .pixels
+                .chunks_exact(2)
+                .map(|bytes| {
+                    let value = half::f16::from_le_bytes([bytes[0], bytes[1]]).to_f32();
+                    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The result of a render: the output image plus the ids of the nodes that
+/// had to be rendered because they were not in the cache.
+pub struct RenderOutput {
+    pub image: RenderedImage,
+    pub rendered_nodes: Vec<String>,
+}
+
+/// A thumbnail of one node output.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodePreview {
+    pub node_id: String,
+    pub handle: String,
+    pub image: RenderedImage,
+}
+
+/// The result of a thumbnail render, with the nodes that were not cached.
+pub struct PreviewOutput {
+    pub previews: Vec<NodePreview>,
+    pub rendered_nodes: Vec<String>,
 }
 
 pub struct AppState {
-    pub render_context: Mutex<Option<RenderContext>>,
+    renderer: Renderer,
+    pub registry: Arc<NodeRegistry>,
+    /// The last graph received by `sync_graph`, which the preview commands
+    /// render from.
+    pub graph: Mutex<Option<Graph>>,
+    /// Stats of the last render that finished, whatever it rendered.
+    pub stats: Mutex<Option<RenderStats>>,
 }
 
 impl AppState {
+    /// State whose render events go nowhere, for rendering without the app.
     pub fn new() -> Self {
+        Self::with_events(Box::new(|_| {}))
+    }
+
+    /// State whose render thread reports to `events`.
+    pub fn with_events(events: EventSink) -> Self {
+        let registry = Arc::new(NodeRegistry::builtin());
         Self {
-            render_context: Mutex::new(None),
+            renderer: Renderer::spawn(registry.clone(), events),
+            registry,
+            graph: Mutex::new(None),
+            stats: Mutex::new(None),
         }
     }
 
-    pub async fn initialize(&self) -> Result<String, String> {
-        let instance = Instance::default();
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or("Failed to find an appropriate adapter")?;
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("Pixel Forge Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::downlevel_defaults(),
-                    memory_hints: wgpu::MemoryHints::Performance,
-                },
-                None,
-            )
-            .await
-            .map_err(|e| format!("Failed to create device: {}", e))?;
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Mix Shader"),
-            source: wgpu::ShaderSource::Wgsl(crate::shaders::MIX_SHADER.into()),
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Mix Bind Group Layout"),
-            entries: &[
-                // Texture A
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                // Texture B
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                // Sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                // Uniforms (Factor)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Mix Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let mix_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Mix Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Rgba8Unorm,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
-
-        let info = format!("Initialized WebGPU on: {:?}", adapter.get_info().name);
-
-        let context = RenderContext {
-            instance,
-            adapter,
-            device,
-            queue,
-            mix_pipeline,
-            bind_group_layout,
-        };
-
-        *self.render_context.lock().unwrap() = Some(context);
+    /// Sets up the backend selected by `kind`, replacing the current one and
+    /// everything it has cached. `Auto` falls back to the CPU when no GPU
+    /// adapter or device can be had.
+    pub async fn initialize(&self, kind: BackendKind) -> Result<String, EngineError> {
+        self.renderer.initialize(kind).await
+    }
 
-        Ok(info)
+    /// Keeps `graph` for the preview commands and tells the node processors
+    /// about it. A graph that fails validation leaves the last one in place.
+    pub fn sync(&self, graph: Graph) -> Result<(), EngineError> {
+        self.check(&graph, true)?;
+        self.registry.graph_synced(&graph);
+        *self.graph.lock()? = Some(graph);
+        Ok(())
     }
 
-    pub async fn render(&self, graph: crate::graph::Graph) -> Result<Vec<u8>, String> {
-        let mut context_guard = self.render_context.lock().map_err(|e| e.to_string())?;
-        let context = context_guard.as_mut().ok_or("WebGPU not initialized")?;
+    /// Renders the graph's output node at full size.
+    pub async fn render(&self, graph: Graph) -> Result<RenderOutput, EngineError> {
+        self.check(&graph, true)?;
 
-        let output_node = graph
+        let (output_node, params) = graph
             .nodes
             .iter()
-            .find(|n| n.node_type == "outputNode")
-            .ok_or("No output node found")?;
-
-        let mut encoder = context
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-
-        let mut textures = Vec::new();
-
-        struct Evaluator<'a> {
-            context: &'a RenderContext,
-            encoder: &'a mut wgpu::CommandEncoder,
-            graph: &'a crate::graph::Graph,
-            textures: &'a mut Vec<wgpu::Texture>,
-        }
-
-        impl<'a> Evaluator<'a> {
-            fn create_texture(&mut self, label: &str) -> wgpu::TextureView {
-                let texture = self
-                    .context
-                    .device
-                    .create_texture(&wgpu::TextureDescriptor {
-                        size: wgpu::Extent3d {
-                            width: 256,
-                            height: 256,
-                            depth_or_array_layers: 1,
-                        },
-                        mip_level_count: 1,
-                        sample_count: 1,
-                        dimension: wgpu::TextureDimension::D2,
-                        format: wgpu::TextureFormat::Rgba8Unorm,
-                        usage: wgpu::TextureUsages::TEXTURE_BINDING
-                            | wgpu::TextureUsages::RENDER_ATTACHMENT
-                            | wgpu::TextureUsages::COPY_SRC,
-                        label: Some(label),
-                        view_formats: &[],
-                    });
-                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-                self.textures.push(texture);
-                view
-            }
+            .find_map(|n| match &n.kind {
+                NodeKind::Output(params) => Some((n, params)),
+                _ => None,
+            })
+            .ok_or_else(|| EngineError::diagnostics(vec![Diagnostic::MissingOutput]))?;
 
-            fn evaluate(&mut self, node_id: &str) -> Result<wgpu::TextureView, String> {
-                let node = self.graph.get_node(node_id).ok_or("Node not found")?;
-
-                match node.node_type.as_str() {
-                    "colorNode" => {
-                        let color = node
-                            .data
-                            .color
-                            .as_ref()
-                            .map(|c| wgpu::Color {
-                                r: c.r as f64 / 255.0,
-                                g: c.g as f64 / 255.0,
-                                b: c.b as f64 / 255.0,
-                                a: c.a as f64,
-                            })
-                            .unwrap_or(wgpu::Color::BLACK);
-
-                        let view = self.create_texture(&format!("Color {}", node.data.label));
-
-                        {
-                            let _pass =
-                                self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                                    label: Some("Clear Pass"),
-                                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                        view: &view,
-                                        resolve_target: None,
-                                        ops: wgpu::Operations {
-                                            load: wgpu::LoadOp::Clear(color),
-                                            store: wgpu::StoreOp::Store,
-                                        },
-                                    })],
-                                    depth_stencil_attachment: None,
-                                    timestamp_writes: None,
-                                    occlusion_query_set: None,
-                                });
-                        }
-                        Ok(view)
-                    }
-                    "mixNode" => {
-                        let edge_a = self.graph.edges.iter().find(|e| {
-                            e.target == node.id && e.target_handle.as_deref() == Some("a")
-                        });
-                        let edge_b = self.graph.edges.iter().find(|e| {
-                            e.target == node.id && e.target_handle.as_deref() == Some("b")
-                        });
-
-                        println!(
-                            "MixNode '{}': Edge A found? {}, Edge B found? {}",
-                            node.data.label,
-                            edge_a.is_some(),
-                            edge_b.is_some()
-                        );
-
-                        let view_a = if let Some(e) = edge_a {
-                            self.evaluate(&e.source)?
-                        } else {
-                            self.create_black_texture()?
-                        };
-
-                        let view_b = if let Some(e) = edge_b {
-                            self.evaluate(&e.source)?
-                        } else {
-                            self.create_black_texture()?
-                        };
-
-                        let output_view = self.create_texture(&format!("Mix {}", node.data.label));
-
-                        let factor = node.data.factor.unwrap_or(0.5);
-                        let uniform_data = [factor, 0.0, 0.0, 0.0]; // 16 bytes
-                        let uniform_buffer = self.context.device.create_buffer_init(
-                            &wgpu::util::BufferInitDescriptor {
-                                label: Some("Mix Buffer"),
-                                contents: bytemuck::cast_slice(&uniform_data),
-                                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                            },
-                        );
-
-                        let sampler =
-                            self.context
-                                .device
-                                .create_sampler(&wgpu::SamplerDescriptor {
-                                    address_mode_u: wgpu::AddressMode::ClampToEdge,
-                                    address_mode_v: wgpu::AddressMode::ClampToEdge,
-                                    address_mode_w: wgpu::AddressMode::ClampToEdge,
-                                    mag_filter: wgpu::FilterMode::Linear,
-                                    min_filter: wgpu::FilterMode::Linear,
-                                    mipmap_filter: wgpu::FilterMode::Nearest,
-                                    ..Default::default()
-                                });
-
-                        let bind_group =
-                            self.context
-                                .device
-                                .create_bind_group(&wgpu::BindGroupDescriptor {
-                                    label: Some("Mix Bind Group"),
-                                    layout: &self.context.bind_group_layout,
-                                    entries: &[
-                                        wgpu::BindGroupEntry {
-                                            binding: 0,
-                                            resource: wgpu::BindingResource::TextureView(&view_a),
-                                        },
-                                        wgpu::BindGroupEntry {
-                                            binding: 1,
-                                            resource: wgpu::BindingResource::TextureView(&view_b),
-                                        },
-                                        wgpu::BindGroupEntry {
-                                            binding: 2,
-                                            resource: wgpu::BindingResource::Sampler(&sampler),
-                                        },
-                                        wgpu::BindGroupEntry {
-                                            binding: 3,
-                                            resource: wgpu::BindingResource::Buffer(
-                                                wgpu::BufferBinding {
-                                                    buffer: &uniform_buffer,
-                                                    offset: 0,
-                                                    size: None,
-                                                },
-                                            ),
-                                        },
-                                    ],
-                                });
-
-                        {
-                            let mut pass =
-                                self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                                    label: Some("Mix Pass"),
-                                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                        view: &output_view,
-                                        resolve_target: None,
-                                        ops: wgpu::Operations {
-                                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                                            store: wgpu::StoreOp::Store,
-                                        },
-                                    })],
-                                    depth_stencil_attachment: None,
-                                    timestamp_writes: None,
-                                    occlusion_query_set: None,
-                                });
-
-                            pass.set_pipeline(&self.context.mix_pipeline);
-                            pass.set_bind_group(0, &bind_group, &[]);
-                            pass.draw(0..3, 0..1);
-                        }
-
-                        Ok(output_view)
-                    }
-                    "outputNode" => {
-                        let edge = self.graph.edges.iter().find(|e| e.target == node.id);
-                        if let Some(e) = edge {
-                            self.evaluate(&e.source)
-                        } else {
-                            self.create_black_texture()
-                        }
-                    }
-                    _ => Err(format!("Unknown node type: {}", node.node_type)),
-                }
-            }
+        let target = ReadbackTarget {
+            key: OutputKey::new(&output_node.id, DEFAULT_OUTPUT),
+            max_size: None,
+            encoding: params.encoding,
+        };
+        let (mut images, rendered_nodes) = self
+            .render_targets(Lane::Output, graph, vec![target])
+            .await?;
+        Ok(RenderOutput {
+            image: images.remove(0),
+            rendered_nodes,
+        })
+    }
 
-            fn create_black_texture(&mut self) -> Result<wgpu::TextureView, String> {
-                let view = self.create_texture("Black Default");
-                {
-                    let _pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                        label: Some("Clear Black Pass"),
-                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                            view: &view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                                store: wgpu::StoreOp::Store,
-                            },
-                        })],
-                        depth_stencil_attachment: None,
-                        timestamp_writes: None,
-                        occlusion_query_set: None,
-                    });
-                }
-                Ok(view)
-            }
+    /// Renders output `handle` of `node_id` at full size in `encoding`. The
+    /// graph does not need an output node for this.
+    pub async fn render_node(
+        &self,
+        graph: &Graph,
+        node_id: &str,
+        handle: &str,
+        encoding: OutputEncoding,
+    ) -> Result<RenderOutput, EngineError> {
+        self.check(graph, false)?;
+
+        let node = graph
+            .get_node(node_id)
+            .ok_or_else(|| EngineError::invalid(format!("Node not found: {}", node_id)))?;
+        let processor = self.processor(node)?;
+        if !processor.outputs().iter().any(|port| port.handle == handle) {
+            return Err(EngineError::invalid(format!(
+                "Node '{}' has no output '{}'",
+                node_id, handle
+            )));
         }
 
-        let final_texture_view = {
-            let mut evaluator = Evaluator {
-                context: context,
-                encoder: &mut encoder,
-                graph: &graph,
-                textures: &mut textures,
-            };
-            evaluator.evaluate(&output_node.id)?
+        let target = ReadbackTarget {
+            key: OutputKey::new(node_id, handle),
+            max_size: None,
+            encoding,
         };
+        let lane = Lane::Node {
+            node_id: node_id.to_string(),
+            handle: handle.to_string(),
+        };
+        let (mut images, rendered_nodes) = self
+            .render_targets(lane, graph.clone(), vec![target])
+            .await?;
+        Ok(RenderOutput {
+            image: images.remove(0),
+            rendered_nodes,
+        })
+    }
 
-        // Render result to readback texture using Mix Pipeline (Copy)
-        let result_texture = context.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: 256,
-                height: 256,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: Some("Readback Texture"),
-            view_formats: &[],
-        });
-        let result_view = result_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        // Dummy texture and view for the second input of simple copy (mix with factor 0)
-        let dummy_tex = textures.last().unwrap();
-        let dummy_view = dummy_tex.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let factor = 0.0f32; // Take 100% of A (final_texture_view)
-        let uniform_data = [factor, 0.0, 0.0, 0.0];
-        let uniform_buffer = context
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Copy Buffer"),
-                contents: bytemuck::cast_slice(&uniform_data),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            });
-
-        let sampler = context
-            .device
-            .create_sampler(&wgpu::SamplerDescriptor::default());
-
-        let bind_group = context
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Copy Bind Group"),
-                layout: &context.bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&final_texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&dummy_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: wgpu::BindingResource::Sampler(&sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 3,
-                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                            buffer: &uniform_buffer,
-                            offset: 0,
-                            size: None,
-                        }),
-                    },
-                ],
-            });
-
-        {
-            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Final Copy Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &result_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            pass.set_pipeline(&context.mix_pipeline);
-            pass.set_bind_group(0, &bind_group, &[]);
-            pass.draw(0..3, 0..1);
+    /// Renders a thumbnail of every output of every node in one pass, each
+    /// scaled down to fit within `max_size` × `max_size`.
+    pub async fn render_previews(
+        &self,
+        graph: &Graph,
+        max_size: u32,
+    ) -> Result<PreviewOutput, EngineError> {
+        if max_size == 0 {
+            return Err(EngineError::invalid("Preview size must be at least 1"));
+        }
+        self.check(graph, false)?;
+
+        let mut targets = Vec::new();
+        for node in &graph.nodes {
+            for port in self.processor(node)?.outputs() {
+                targets.push(ReadbackTarget {
+                    key: OutputKey::new(&node.id, port.handle),
+                    max_size: Some(max_size),
+                    encoding: OutputEncoding::Srgb8,
+                });
+            }
         }
 
-        // Readback
-        let u32_size = std::mem::size_of::<u32>() as u32;
-        let output_buffer_size = (u32_size * 256 * 256) as wgpu::BufferAddress;
-        let output_buffer_desc = wgpu::BufferDescriptor {
-            size: output_buffer_size,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            label: Some("Output Buffer"),
-            mapped_at_creation: false,
+        let (images, rendered_nodes) = self
+            .render_targets(Lane::Previews, graph.clone(), targets.clone())
+            .await?;
+        let previews = targets
+            .into_iter()
+            .zip(images)
+            .map(|(target, image)| NodePreview {
+                node_id: target.key.node_id,
+                handle: target.key.handle,
+                image,
+            })
+            .collect();
+        Ok(PreviewOutput {
+            previews,
+            rendered_nodes,
+        })
+    }
+
+    /// Validates `graph`. Previews of single nodes do not need the graph to
+    /// have exactly one output node, so those diagnostics are only reported
+    /// when `require_output` is set.
+    fn check(&self, graph: &Graph, require_output: bool) -> Result<(), EngineError> {
+        let Err(diagnostics) = graph.validate(&self.registry) else {
+            return Ok(());
         };
-        let output_buffer = context.device.create_buffer(&output_buffer_desc);
-
-        encoder.copy_texture_to_buffer(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &result_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            wgpu::ImageCopyBuffer {
-                buffer: &output_buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(u32_size * 256),
-                    rows_per_image: Some(256),
-                },
-            },
-            wgpu::Extent3d {
-                width: 256,
-                height: 256,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        context.queue.submit(Some(encoder.finish()));
-
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        context.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().map_err(|e| e.to_string())?;
-
-        let data = buffer_slice.get_mapped_range();
-        let result = data.to_vec();
-        drop(data);
-        output_buffer.unmap();
-
-        Ok(result)
+        let diagnostics: Vec<_> = diagnostics
+            .into_iter()
+            .filter(|d| {
+                require_output
+                    || !matches!(
+                        d,
+                        Diagnostic::MissingOutput | Diagnostic::MultipleOutputs { .. }
+                    )
+            })
+            .collect();
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(EngineError::diagnostics(diagnostics))
+        }
+    }
+
+    fn processor(&self, node: &Node) -> Result<&dyn NodeProcessor, EngineError> {
+        let type_name = node.kind.type_name();
+        self.registry
+            .get(type_name)
+            .ok_or_else(|| EngineError::node(&node.id, format!("Unknown node type: {}", type_name)))
+    }
+
+    async fn render_targets(
+        &self,
+        lane: Lane,
+        graph: Graph,
+        targets: Vec<ReadbackTarget>,
+    ) -> Result<(Vec<RenderedImage>, Vec<String>), EngineError> {
+        let result = self.renderer.render(lane, graph, targets).await?;
+        *self.stats.lock()? = Some(result.stats);
+        Ok((result.images, result.rendered_nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::two_colors_mixed;
+    use crate::graph::Size;
+
+    fn sorted(mut ids: Vec<String>) -> Vec<String> {
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn only_edited_nodes_and_their_dependents_render_again() {
+        let state = AppState::new();
+        state.initialize(BackendKind::Cpu).await.unwrap();
+
+        let first = state.render(two_colors_mixed(255)).await.unwrap();
+        assert_eq!(sorted(first.rendered_nodes), ["blue", "mix", "out", "red"]);
+
+        let unchanged = state.render(two_colors_mixed(255)).await.unwrap();
+        assert!(unchanged.rendered_nodes.is_empty());
+
+        let edited = state.render(two_colors_mixed(128)).await.unwrap();
+        assert_eq!(sorted(edited.rendered_nodes), ["blue", "mix", "out"]);
+        let stats = state.stats.lock().unwrap().clone().unwrap();
+        assert_eq!(sorted(stats.rendered_nodes), ["blue", "mix", "out"]);
+    }
+
+    #[test]
+    fn invalid_graph_does_not_replace_the_synced_one() {
+        let state = AppState::new();
+        state.sync(two_colors_mixed(255)).unwrap();
+
+        let mut invalid = two_colors_mixed(128);
+        invalid.canvas = Size::new(0, 0);
+        assert!(state.sync(invalid).is_err());
+        let synced = state.graph.lock().unwrap().clone().unwrap();
+        assert_eq!(synced.canvas, Size::new(8, 8));
     }
 }
@@ -0,0 +1,96 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Where node durations come from.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TimingSource {
+    /// GPU timestamps written before and after each node's passes.
+    Gpu,
+    /// Wall-clock time spent evaluating each node. On the GPU backend this
+    /// only covers recording the node's commands, not running them, and is
+    /// used when the adapter cannot write timestamps.
+    Cpu,
+}
+
+/// How one node of a render fared.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStats {
+    pub node_id: String,
+    pub node_type: String,
+    /// Whether the node's outputs were taken from the cache.
+    pub cached: bool,
+    /// How long the node took, unset for cached nodes.
+    pub duration_ms: Option<f64>,
+}
+
+/// What one render cost, returned by `get_render_stats`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderStats {
+    pub backend: String,
+    pub timing: TimingSource,
+    /// Wall-clock time of the whole render, readback included.
+    pub total_ms: f64,
+    /// Every node the render needed, in the order they were scheduled.
+    pub nodes: Vec<NodeStats>,
+    /// The nodes that were rendered rather than taken from the cache, in
+    /// the order they were scheduled.
+    pub rendered_nodes: Vec<String>,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    /// Bytes of node outputs the cache holds after the render.
+    pub cached_bytes: u64,
+    /// Bytes of free textures the pool keeps for reuse after the render.
+    pub pooled_bytes: u64,
+}
+
+impl RenderStats {
+    pub fn new(
+        backend: String,
+        timing: TimingSource,
+        total: Duration,
+        nodes: Vec<NodeStats>,
+        cached_bytes: u64,
+        pooled_bytes: u64,
+    ) -> Self {
+        let cache_hits = nodes.iter().filter(|node| node.cached).count();
+        let rendered_nodes = nodes
+            .iter()
+            .filter(|node| !node.cached)
+            .map(|node| node.node_id.clone())
+            .collect();
+        Self {
+            backend,
+            timing,
+            total_ms: milliseconds(total),
+            cache_misses: nodes.len() - cache_hits,
+            cache_hits,
+            nodes,
+            rendered_nodes,
+            cached_bytes,
+            pooled_bytes,
+        }
+    }
+}
+
+pub fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Adds up the size of `images`, counting images shared by several node
+/// outputs once.
+pub fn unique_bytes<'a, T: 'a>(
+    images: impl IntoIterator<Item = &'a Arc<T>>,
+    bytes: impl Fn(&T) -> u64,
+) -> u64 {
+    let mut seen = HashSet::new();
+    images
+        .into_iter()
+        .filter(|image| seen.insert(Arc::as_ptr(image)))
+        .map(|image| bytes(image))
+        .sum()
+}
@@ -0,0 +1,233 @@
+use crate::graph::Size;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureKey {
+    pub size: Size,
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsages,
+}
+
+/// What the pool needs to know about the textures it keeps.
+pub trait PooledTexture {
+    fn key(&self) -> TextureKey;
+
+    /// The memory taken by the texture's pixels.
+    fn bytes(&self) -> u64;
+}
+
+impl PooledTexture for wgpu::Texture {
+    fn key(&self) -> TextureKey {
+        TextureKey {
+            size: Size::new(self.width(), self.height()),
+            format: self.format(),
+            usage: self.usage(),
+        }
+    }
+
+    fn bytes(&self) -> u64 {
+        texture_bytes(self)
+    }
+}
+
+/// The memory taken by the pixels of `texture`.
+pub fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let bytes_per_pixel = texture.format().block_copy_size(None).unwrap_or(0);
+    u64::from(bytes_per_pixel) * u64::from(texture.width()) * u64::from(texture.height())
+}
+
+struct FreeTexture<T> {
+    texture: Arc<T>,
+    /// The render it was released in.
+    released: u64,
+}
+
+/// Hands out textures for node outputs and other per-render targets, and
+/// takes them back for reuse once nothing holds them any more.
+///
+/// Free textures survive one full render without being reused before they
+/// are dropped, so a graph that renders the same sizes every time allocates
+/// nothing, while memory from a graph that shrank is given back.
+pub struct TexturePool<T = wgpu::Texture> {
+    free: RefCell<HashMap<TextureKey, Vec<FreeTexture<T>>>>,
+    frame: Cell<u64>,
+}
+
+impl<T> Default for TexturePool<T> {
+    fn default() -> Self {
+        Self {
+            free: RefCell::default(),
+            frame: Cell::default(),
+        }
+    }
+}
+
+impl TexturePool {
+    /// Returns a free texture matching `key`, or a new one labelled `label`.
+    pub fn acquire(
+        &self,
+        device: &wgpu::Device,
+        label: &str,
+        key: TextureKey,
+    ) -> Arc<wgpu::Texture> {
+        if let Some(texture) = self.take(key) {
+            return texture;
+        }
+        Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+            size: key.size.extent(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: key.format,
+            usage: key.usage,
+            label: Some(label),
+            view_formats: &[],
+        }))
+    }
+}
+
+impl<T: PooledTexture> TexturePool<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a free texture matching `key`, if there is one.
+    fn take(&self, key: TextureKey) -> Option<Arc<T>> {
+        let free = self.free.borrow_mut().get_mut(&key).and_then(Vec::pop)?;
+        Some(free.texture)
+    }
+
+    /// Takes `texture` back if this was the last reference to it. Textures
+    /// still held elsewhere, e.g. by the node cache, are left alone.
+    pub fn release(&self, texture: Arc<T>) {
+        if Arc::strong_count(&texture) != 1 {
+            return;
+        }
+        self.free
+            .borrow_mut()
+            .entry(texture.key())
+            .or_default()
+            .push(FreeTexture {
+                texture,
+                released: self.frame.get(),
+            });
+    }
+
+    /// Bytes of the free textures kept for reuse.
+    pub fn free_bytes(&self) -> u64 {
+        self.free
+            .borrow()
+            .values()
+            .flatten()
+            .map(|free| free.texture.bytes())
+            .sum()
+    }
+
+    /// Starts a render, which ends when the returned guard is dropped, so
+    /// failed and cancelled renders end theirs too.
+    pub fn frame(&self) -> Frame<'_, T> {
+        Frame(self)
+    }
+
+    /// Marks the end of a render, dropping textures that were already free
+    /// when it started and were not reused during it.
+    fn end_frame(&self) {
+        let frame = self.frame.get();
+        self.free.borrow_mut().retain(|_, textures| {
+            textures.retain(|free| free.released >= frame);
+            !textures.is_empty()
+        });
+        self.frame.set(frame + 1);
+    }
+}
+
+/// A render in progress; see [`TexturePool::frame`].
+pub struct Frame<'a, T: PooledTexture>(&'a TexturePool<T>);
+
+impl<T: PooledTexture> Drop for Frame<'_, T> {
+    fn drop(&mut self) {
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for a GPU texture, so the pool can be tested without one.
+    struct FakeTexture(TextureKey);
+
+    impl PooledTexture for FakeTexture {
+        fn key(&self) -> TextureKey {
+            self.0
+        }
+
+        fn bytes(&self) -> u64 {
+            u64::from(self.0.size.width) * u64::from(self.0.size.height) * 4
+        }
+    }
+
+    fn key(width: u32) -> TextureKey {
+        TextureKey {
+            size: Size::new(width, 4),
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        }
+    }
+
+    #[test]
+    fn texture_still_held_is_not_reused() {
+        let pool = TexturePool::new();
+        let texture = Arc::new(FakeTexture(key(4)));
+        let held = texture.clone();
+
+        pool.release(texture);
+        assert!(pool.take(key(4)).is_none());
+        assert_eq!(pool.free_bytes(), 0);
+
+        pool.release(held.clone());
+        assert!(pool.take(key(4)).is_none());
+        drop(held);
+    }
+
+    #[test]
+    fn released_texture_is_reused_for_the_same_key_only() {
+        let pool = TexturePool::new();
+        let texture = Arc::new(FakeTexture(key(4)));
+        let address = Arc::as_ptr(&texture);
+
+        pool.release(texture);
+        assert_eq!(pool.free_bytes(), 64);
+        assert!(pool.take(key(8)).is_none());
+        let reused = pool.take(key(4)).unwrap();
+        assert_eq!(Arc::as_ptr(&reused), address);
+        assert!(pool.take(key(4)).is_none());
+    }
+
+    #[test]
+    fn free_texture_is_dropped_after_a_render_without_reuse() {
+        let pool = TexturePool::new();
+        pool.release(Arc::new(FakeTexture(key(4))));
+
+        pool.end_frame();
+        assert_eq!(pool.free_bytes(), 64);
+        pool.end_frame();
+        assert_eq!(pool.free_bytes(), 0);
+    }
+
+    #[test]
+    fn render_that_fails_still_ends_its_frame() {
+        let pool = TexturePool::new();
+        pool.release(Arc::new(FakeTexture(key(4))));
+
+        let render = || -> Result<(), String> {
+            let _frame = pool.frame();
+            Err("cancelled".to_string())
+        };
+        render().unwrap_err();
+        render().unwrap_err();
+        assert_eq!(pool.free_bytes(), 0);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::error::EngineError;
+use crate::state::RenderContext;
+use std::collections::HashMap;
+
+/// The features [`NodeTimer`] needs, requested wherever the adapter has them.
+pub const FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
+/// Writes a pair of GPU timestamps around the passes of each node rendered
+/// into one command encoder.
+pub struct NodeTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    /// The nodes timed so far, in the order their pairs were written.
+    nodes: Vec<String>,
+}
+
+impl NodeTimer {
+    /// A timer for up to `capacity` nodes, or `None` if the device cannot
+    /// write timestamps between passes or that many do not fit a query set.
+    pub fn new(device: &wgpu::Device, capacity: usize) -> Option<Self> {
+        let count = u32::try_from(capacity * 2).ok()?;
+        if !device.features().contains(FEATURES)
+            || count == 0
+            || count > wgpu::QUERY_SET_MAX_QUERIES
+        {
+            return None;
+        }
+
+        let size = u64::from(count) * u64::from(wgpu::QUERY_SIZE);
+        Some(Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Node Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Read Buffer"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            nodes: Vec::new(),
+        })
+    }
+
+    /// Marks the start of the next node's passes.
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.write_timestamp(&self.query_set, self.next_query());
+    }
+
+    /// Marks the end of `node_id`'s passes, which must follow [`Self::begin`].
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder, node_id: &str) {
+        encoder.write_timestamp(&self.query_set, self.next_query() + 1);
+        self.nodes.push(node_id.to_string());
+    }
+
+    fn next_query(&self) -> u32 {
+        self.nodes.len() as u32 * 2
+    }
+
+    /// Copies the timestamps written so far where [`Self::read`] can map
+    /// them. Recorded after the last node, before the encoder is submitted.
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let count = self.next_query();
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.read_buffer,
+            0,
+            u64::from(count) * u64::from(wgpu::QUERY_SIZE),
+        );
+    }
+
+    /// Waits for the submitted work and returns how long each timed node
+    /// took on the GPU, in milliseconds.
+    pub fn read(self, context: &RenderContext) -> Result<HashMap<String, f64>, EngineError> {
+        if self.nodes.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let size = u64::from(self.next_query()) * u64::from(wgpu::QUERY_SIZE);
+        let buffer_slice = self.read_buffer.slice(..size);
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            // The receiver only goes away if `read` has already failed.
+            let _ = tx.send(result);
+        });
+        context.device.poll(wgpu::Maintain::Wait);
+        rx.recv()
+            .map_err(|_| EngineError::gpu("Timestamp buffer was dropped before it was mapped"))?
+            .map_err(|e| EngineError::gpu(format!("Failed to map timestamp buffer: {}", e)))?;
+
+        // Ticks to milliseconds.
+        let period = f64::from(context.queue.get_timestamp_period()) / 1_000_000.0;
+        let data = buffer_slice.get_mapped_range();
+        let timestamps: &[u64] = bytemuck::cast_slice(&data);
+        let durations = self
+            .nodes
+            .into_iter()
+            .zip(timestamps.chunks_exact(2))
+            .map(|(node_id, pair)| (node_id, pair[1].saturating_sub(pair[0]) as f64 * period))
+            .collect();
+        drop(data);
+        self.read_buffer.unmap();
+
+        Ok(durations)
+    }
+}
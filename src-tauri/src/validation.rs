@@ -0,0 +1,623 @@
+use crate::graph::{Graph, NodeKind, MAX_SIZE};
+use crate::nodes::NodeRegistry;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Which end of an edge a diagnostic is about.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EdgeEnd {
+    Source,
+    Target,
+}
+
+/// A single problem found by [`Graph::validate`], carrying the ids of every
+/// node and edge involved so the editor can highlight them.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Diagnostic {
+    #[serde(rename_all = "camelCase")]
+    DuplicateNodeId {
+        node_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    DuplicateEdgeId {
+        edge_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    DanglingEdge {
+        edge_id: String,
+        end: EdgeEnd,
+        node_id: String,
+    },
+    /// An edge names a handle its node type does not have.
+    #[serde(rename_all = "camelCase")]
+    UnknownHandle {
+        edge_id: String,
+        end: EdgeEnd,
+        node_id: String,
+        handle: String,
+    },
+    /// A node has sampler settings for an input its type does not have.
+    #[serde(rename_all = "camelCase")]
+    UnknownSamplerInput {
+        node_id: String,
+        handle: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    MultipleInputEdges {
+        node_id: String,
+        handle: String,
+        edge_ids: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Cycle {
+        node_ids: Vec<String>,
+        edge_ids: Vec<String>,
+    },
+    /// A zero width or height, on the canvas when `node_id` is unset.
+    #[serde(rename_all = "camelCase")]
+    EmptySize {
+        node_id: Option<String>,
+        width: u32,
+        height: u32,
+    },
+    /// A width or height above `max`, on the canvas when `node_id` is unset.
+    #[serde(rename_all = "camelCase")]
+    SizeTooLarge {
+        node_id: Option<String>,
+        width: u32,
+        height: u32,
+        max: u32,
+    },
+    MissingOutput,
+    #[serde(rename_all = "camelCase")]
+    MultipleOutputs {
+        node_ids: Vec<String>,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::DuplicateNodeId { node_id } => {
+                write!(f, "duplicate node id '{}'", node_id)
+            }
+            Diagnostic::DuplicateEdgeId { edge_id } => {
+                write!(f, "duplicate edge id '{}'", edge_id)
+            }
+            Diagnostic::DanglingEdge {
+                edge_id,
+                end,
+                node_id,
+            } => {
+                let end = match end {
+                    EdgeEnd::Source => "source",
+                    EdgeEnd::Target => "target",
+                };
+                write!(
+                    f,
+                    "edge '{}' has unknown {} node '{}'",
+                    edge_id, end, node_id
+                )
+            }
+            Diagnostic::UnknownHandle {
+                edge_id,
+                end,
+                node_id,
+                handle,
+            } => {
+                let kind = match end {
+                    EdgeEnd::Source => "output",
+                    EdgeEnd::Target => "input",
+                };
+                write!(
+                    f,
+                    "edge '{}' uses unknown {} '{}' of node '{}'",
+                    edge_id, kind, handle, node_id
+                )
+            }
+            Diagnostic::UnknownSamplerInput { node_id, handle } => write!(
+                f,
+                "node '{}' has sampler settings for unknown input '{}'",
+                node_id, handle
+            ),
+            Diagnostic::MultipleInputEdges {
+                node_id,
+                handle,
+                edge_ids,
+            } => write!(
+                f,
+                "input '{}' of node '{}' has {} edges: {}",
+                handle,
+                node_id,
+                edge_ids.len(),
+                edge_ids.join(", ")
+            ),
+            Diagnostic::Cycle { node_ids, .. } => {
+                write!(f, "cycle through nodes {}", node_ids.join(" -> "))
+            }
+            Diagnostic::EmptySize {
+                node_id,
+                width,
+                height,
+            } => match node_id {
+                Some(node_id) => {
+                    write!(f, "node '{}' has empty size {}x{}", node_id, width, height)
+                }
+                None => write!(f, "canvas has empty size {}x{}", width, height),
+            },
+            Diagnostic::SizeTooLarge {
+                node_id,
+                width,
+                height,
+                max,
+            } => match node_id {
+                Some(node_id) => write!(
+                    f,
+                    "node '{}' has size {}x{}, larger than {}",
+                    node_id, width, height, max
+                ),
+                None => write!(
+                    f,
+                    "canvas has size {}x{}, larger than {}",
+                    width, height, max
+                ),
+            },
+            Diagnostic::MissingOutput => write!(f, "no output node found"),
+            Diagnostic::MultipleOutputs { node_ids } => {
+                write!(f, "more than one output node: {}", node_ids.join(", "))
+            }
+        }
+    }
+}
+
+/// A node id and one of its input handles.
+type InputSlot<'a> = (&'a str, &'a str);
+
+impl Graph {
+    /// Checks the graph for structural problems before it is rendered.
+    ///
+    /// Every problem is reported, not just the first one, so the frontend can
+    /// mark all broken nodes and edges at once. Edge handles are checked
+    /// against the ports the node types declare in `registry`.
+    pub fn validate(&self, registry: &NodeRegistry) -> Result<(), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+
+        let mut node_ids = HashSet::new();
+        for node in &self.nodes {
+            if !node_ids.insert(node.id.as_str()) {
+                diagnostics.push(Diagnostic::DuplicateNodeId {
+                    node_id: node.id.clone(),
+                });
+            }
+        }
+
+        let mut edge_ids = HashSet::new();
+        for edge in &self.edges {
+            if !edge_ids.insert(edge.id.as_str()) {
+                diagnostics.push(Diagnostic::DuplicateEdgeId {
+                    edge_id: edge.id.clone(),
+                });
+            }
+        }
+
+        // Edges with a missing endpoint are reported here and left out of the
+        // remaining checks.
+        let mut edges = Vec::new();
+        for edge in &self.edges {
+            let mut dangling = false;
+            for (end, node_id) in [
+                (EdgeEnd::Source, &edge.source),
+                (EdgeEnd::Target, &edge.target),
+            ] {
+                if !node_ids.contains(node_id.as_str()) {
+                    dangling = true;
+                    diagnostics.push(Diagnostic::DanglingEdge {
+                        edge_id: edge.id.clone(),
+                        end,
+                        node_id: node_id.clone(),
+                    });
+                }
+            }
+            if !dangling {
+                edges.push(edge);
+            }
+        }
+
+        for edge in &edges {
+            let ends = [
+                (EdgeEnd::Source, &edge.source, edge.output_handle()),
+                (EdgeEnd::Target, &edge.target, edge.input_handle()),
+            ];
+            for (end, node_id, handle) in ends {
+                let Some(processor) = self
+                    .get_node(node_id)
+                    .and_then(|node| registry.get(node.kind.type_name()))
+                else {
+                    continue;
+                };
+                let ports = match end {
+                    EdgeEnd::Source => processor.outputs(),
+                    EdgeEnd::Target => processor.inputs(),
+                };
+                if !ports.iter().any(|port| port.handle == handle) {
+                    diagnostics.push(Diagnostic::UnknownHandle {
+                        edge_id: edge.id.clone(),
+                        end,
+                        node_id: node_id.clone(),
+                        handle: handle.to_string(),
+                    });
+                }
+            }
+        }
+
+        for node in &self.nodes {
+            let Some(processor) = registry.get(node.kind.type_name()) else {
+                continue;
+            };
+            for handle in node.samplers.keys() {
+                if !processor.inputs().iter().any(|port| port.handle == handle) {
+                    diagnostics.push(Diagnostic::UnknownSamplerInput {
+                        node_id: node.id.clone(),
+                        handle: handle.clone(),
+                    });
+                }
+            }
+        }
+
+        // Grouped in first-seen order so diagnostics come out deterministically.
+        // Keyed on the resolved handle, so an edge without one and an edge
+        // naming the default input count as the same input.
+        let mut inputs: Vec<(InputSlot, Vec<String>)> = Vec::new();
+        let mut input_index: HashMap<InputSlot, usize> = HashMap::new();
+        for edge in &edges {
+            let key = (edge.target.as_str(), edge.input_handle());
+            let index = *input_index.entry(key).or_insert_with(|| {
+                inputs.push((key, Vec::new()));
+                inputs.len() - 1
+            });
+            inputs[index].1.push(edge.id.clone());
+        }
+        for ((node_id, handle), edge_ids) in inputs {
+            if edge_ids.len() > 1 {
+                diagnostics.push(Diagnostic::MultipleInputEdges {
+                    node_id: node_id.to_string(),
+                    handle: handle.to_string(),
+                    edge_ids,
+                });
+            }
+        }
+
+        diagnostics.extend(find_cycles(&edges));
+
+        let sizes = std::iter::once((None, self.canvas)).chain(
+            self.nodes
+                .iter()
+                .filter_map(|n| n.size.map(|size| (Some(n.id.clone()), size))),
+        );
+        for (node_id, size) in sizes {
+            if size.width == 0 || size.height == 0 {
+                diagnostics.push(Diagnostic::EmptySize {
+                    node_id,
+                    width: size.width,
+                    height: size.height,
+                });
+            } else if size.too_large() {
+                diagnostics.push(Diagnostic::SizeTooLarge {
+                    node_id,
+                    width: size.width,
+                    height: size.height,
+                    max: MAX_SIZE,
+                });
+            }
+        }
+
+        let outputs: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|n| matches!(n.kind, NodeKind::Output(_)))
+            .map(|n| n.id.clone())
+            .collect();
+        match outputs.len() {
+            0 => diagnostics.push(Diagnostic::MissingOutput),
+            1 => {}
+            _ => diagnostics.push(Diagnostic::MultipleOutputs { node_ids: outputs }),
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+}
+
+/// Finds cycles with an iterative depth-first search, reporting one cycle per
+/// back edge. Iterative so that deep graphs cannot overflow the stack.
+fn find_cycles(edges: &[&crate::graph::Edge]) -> Vec<Diagnostic> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Visiting,
+        Done,
+    }
+
+    let mut outgoing: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, edge) in edges.iter().enumerate() {
+        outgoing
+            .entry(edge.source.as_str())
+            .or_default()
+            .push(index);
+    }
+
+    let mut roots: Vec<&str> = edges.iter().map(|e| e.source.as_str()).collect();
+    roots.dedup();
+
+    let mut marks: HashMap<&str, Mark> = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for root in roots {
+        if marks.contains_key(root) {
+            continue;
+        }
+
+        // Each frame is a node plus the position of the next outgoing edge to
+        // follow; `path` holds the edge used to enter each frame but the root.
+        let mut stack: Vec<(&str, usize)> = vec![(root, 0)];
+        let mut path: Vec<usize> = Vec::new();
+        marks.insert(root, Mark::Visiting);
+
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&edge_index) = outgoing.get(node).and_then(|out| out.get(*next)) else {
+                marks.insert(node, Mark::Done);
+                stack.pop();
+                path.pop();
+                continue;
+            };
+            *next += 1;
+
+            let target = edges[edge_index].target.as_str();
+            match marks.get(target) {
+                None => {
+                    marks.insert(target, Mark::Visiting);
+                    stack.push((target, 0));
+                    path.push(edge_index);
+                }
+                Some(Mark::Visiting) => {
+                    let start = stack.iter().position(|(id, _)| *id == target).unwrap_or(0);
+                    let mut node_ids: Vec<String> = stack[start..]
+                        .iter()
+                        .map(|(id, _)| id.to_string())
+                        .collect();
+                    node_ids.push(target.to_string());
+                    let mut edge_ids: Vec<String> =
+                        path[start..].iter().map(|&i| edges[i].id.clone()).collect();
+                    edge_ids.push(edges[edge_index].id.clone());
+                    diagnostics.push(Diagnostic::Cycle { node_ids, edge_ids });
+                }
+                Some(Mark::Done) => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::graph_from;
+    use crate::graph::Size;
+    use serde_json::json;
+
+    fn validate(graph: &Graph) -> Vec<Diagnostic> {
+        graph
+            .validate(&NodeRegistry::builtin())
+            .err()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn valid_graph_has_no_diagnostics() {
+        let graph = graph_from(
+            &[
+                ("color", "colorNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[("e1", "color", "out", None)],
+        );
+        assert_eq!(validate(&graph), []);
+    }
+
+    #[test]
+    fn cycle_is_reported_with_its_path() {
+        let graph = graph_from(
+            &[
+                ("a", "mixNode", json!({})),
+                ("b", "mixNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "a", "b", Some("a")),
+                ("e2", "b", "a", Some("a")),
+                ("e3", "b", "out", None),
+            ],
+        );
+        assert_eq!(
+            validate(&graph),
+            [Diagnostic::Cycle {
+                node_ids: vec!["a".into(), "b".into(), "a".into()],
+                edge_ids: vec!["e1".into(), "e2".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn dangling_edge_is_reported() {
+        let graph = graph_from(
+            &[
+                ("color", "colorNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[("e1", "color", "out", None), ("e2", "color", "ghost", None)],
+        );
+        assert_eq!(
+            validate(&graph),
+            [Diagnostic::DanglingEdge {
+                edge_id: "e2".into(),
+                end: EdgeEnd::Target,
+                node_id: "ghost".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_output_is_reported() {
+        let graph = graph_from(&[("color", "colorNode", json!({}))], &[]);
+        assert_eq!(validate(&graph), [Diagnostic::MissingOutput]);
+    }
+
+    #[test]
+    fn empty_size_is_reported() {
+        let mut graph = graph_from(
+            &[
+                ("color", "colorNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[("e1", "color", "out", None)],
+        );
+        graph.canvas = Size::new(0, 16);
+        graph.nodes[0].size = Some(Size::new(16, 0));
+        assert_eq!(
+            validate(&graph),
+            [
+                Diagnostic::EmptySize {
+                    node_id: None,
+                    width: 0,
+                    height: 16,
+                },
+                Diagnostic::EmptySize {
+                    node_id: Some("color".into()),
+                    width: 16,
+                    height: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn size_too_large_is_reported() {
+        let mut graph = graph_from(
+            &[
+                ("color", "colorNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[("e1", "color", "out", None)],
+        );
+        graph.canvas = Size::new(MAX_SIZE + 1, 16);
+        graph.nodes[0].size = Some(Size::new(16, MAX_SIZE + 1));
+        graph.nodes[1].size = Some(Size::new(MAX_SIZE, MAX_SIZE));
+        assert_eq!(
+            validate(&graph),
+            [
+                Diagnostic::SizeTooLarge {
+                    node_id: None,
+                    width: MAX_SIZE + 1,
+                    height: 16,
+                    max: MAX_SIZE,
+                },
+                Diagnostic::SizeTooLarge {
+                    node_id: Some("color".into()),
+                    width: 16,
+                    height: MAX_SIZE + 1,
+                    max: MAX_SIZE,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_handle_is_reported() {
+        let graph = graph_from(
+            &[
+                ("a", "colorNode", json!({})),
+                ("mix", "mixNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[("e1", "a", "mix", Some("c")), ("e2", "mix", "out", None)],
+        );
+        assert_eq!(
+            validate(&graph),
+            [Diagnostic::UnknownHandle {
+                edge_id: "e1".into(),
+                end: EdgeEnd::Target,
+                node_id: "mix".into(),
+                handle: "c".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn default_and_named_default_input_are_the_same_input() {
+        let graph = graph_from(
+            &[
+                ("a", "colorNode", json!({})),
+                ("b", "colorNode", json!({})),
+                ("out", "outputNode", json!({})),
+            ],
+            &[("e1", "a", "out", None), ("e2", "b", "out", Some("in"))],
+        );
+        assert_eq!(
+            validate(&graph),
+            [Diagnostic::MultipleInputEdges {
+                node_id: "out".into(),
+                handle: "in".into(),
+                edge_ids: vec!["e1".into(), "e2".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let graph = graph_from(
+            &[
+                ("a", "colorNode", json!({})),
+                ("a", "colorNode", json!({})),
+                ("mix", "mixNode", json!({})),
+                ("out", "outputNode", json!({})),
+                ("out2", "outputNode", json!({})),
+            ],
+            &[
+                ("e1", "a", "mix", Some("a")),
+                ("e1", "a", "mix", Some("a")),
+                ("e2", "ghost", "out", None),
+            ],
+        );
+        assert_eq!(
+            validate(&graph),
+            [
+                Diagnostic::DuplicateNodeId {
+                    node_id: "a".into()
+                },
+                Diagnostic::DuplicateEdgeId {
+                    edge_id: "e1".into()
+                },
+                Diagnostic::DanglingEdge {
+                    edge_id: "e2".into(),
+                    end: EdgeEnd::Source,
+                    node_id: "ghost".into(),
+                },
+                Diagnostic::MultipleInputEdges {
+                    node_id: "mix".into(),
+                    handle: "a".into(),
+                    edge_ids: vec!["e1".into(), "e1".into()],
+                },
+                Diagnostic::MultipleOutputs {
+                    node_ids: vec!["out".into(), "out2".into()],
+                },
+            ]
+        );
+    }
+}
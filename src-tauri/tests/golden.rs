@@ -0,0 +1,204 @@
+//! Golden-image tests.
+//!
+//! Every graph in `tests/fixtures/*.json`, in the format `sync_graph`
+//! accepts, is rendered headlessly and compared with the PNG of the same name
+//! next to it, after converting the output encoding to RGBA8. Each channel
+//! may differ by up to [`TOLERANCE`]. Files the graphs load, kept in
+//! `tests/fixtures/images/`, are referenced relative to the crate root, which
+//! tests run from. Fixtures named `*_tileable` must also wrap seamlessly:
+//! the step from the last column or row back to the first may be no larger
+//! than the largest step between neighbouring pixels inside the image.
+//!
+//! On a mismatch the rendered image and a diff, with out-of-tolerance pixels
+//! in red, are written to `target/tmp/golden/`. Run with
+//! `PIXEL_FORGE_BLESS=1` to write the CPU backend's output as the new
+//! references instead.
+
+use image::{Rgba, RgbaImage};
+use pixel_forge_lib::headless::render_graph_json;
+use pixel_forge_lib::{BackendKind, EngineError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TOLERANCE: u8 = 2;
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut fixtures: Vec<_> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn bless() -> bool {
+    std::env::var_os("PIXEL_FORGE_BLESS").is_some_and(|v| v != "0")
+}
+
+/// Describes how `actual` differs from `expected`, if it does by more than
+/// the tolerance.
+fn compare(expected: &RgbaImage, actual: &RgbaImage) -> Option<String> {
+    if expected.dimensions() != actual.dimensions() {
+        return Some(format!(
+            "size is {:?}, expected {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+
+    let mut mismatched = 0;
+    let mut max_delta = 0;
+    for (e, a) in expected.pixels().zip(actual.pixels()) {
+        let delta = channel_delta(e, a);
+        max_delta = max_delta.max(delta);
+        if delta > TOLERANCE {
+            mismatched += 1;
+        }
+    }
+    (mismatched > 0).then(|| {
+        format!(
+            "{} pixel(s) differ by more than {}, by up to {}",
+            mismatched, TOLERANCE, max_delta
+        )
+    })
+}
+
+fn channel_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    a.0.iter()
+        .zip(b.0.iter())
+        .map(|(a, b)| a.abs_diff(*b))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Describes the seam of `image`, if wrapping it around either axis jumps
+/// further than any two neighbouring pixels inside it do.
+fn seam(image: &RgbaImage) -> Option<String> {
+    let (width, height) = image.dimensions();
+    let step = |(x, y): (u32, u32), (nx, ny): (u32, u32)| {
+        channel_delta(image.get_pixel(x, y), image.get_pixel(nx, ny))
+    };
+    let (mut inside, mut across) = (0, 0);
+    for y in 0..height {
+        for x in 0..width {
+            let right = step((x, y), ((x + 1) % width, y));
+            let below = step((x, y), (x, (y + 1) % height));
+            if x + 1 == width {
+                across = across.max(right);
+            } else {
+                inside = inside.max(right);
+            }
+            if y + 1 == height {
+                across = across.max(below);
+            } else {
+                inside = inside.max(below);
+            }
+        }
+    }
+    (across > inside).then(|| {
+        format!(
+            "does not tile: the edges differ by up to {}, neighbouring pixels by up to {}",
+            across, inside
+        )
+    })
+}
+
+/// Red where `actual` is out of tolerance, a dimmed gray version of
+/// `expected` elsewhere. Both images must be the same size.
+fn diff_image(expected: &RgbaImage, actual: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let e = expected.get_pixel(x, y);
+        if channel_delta(e, actual.get_pixel(x, y)) > TOLERANCE {
+            Rgba([255, 0, 0, 255])
+        } else {
+            let gray = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 9) as u8;
+            Rgba([gray, gray, gray, 255])
+        }
+    })
+}
+
+fn check_fixtures(kind: BackendKind, label: &str) {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let bless = bless() && kind == BackendKind::Cpu;
+    let mut failures = Vec::new();
+
+    for fixture in fixtures() {
+        let name = fixture.file_stem().unwrap().to_string_lossy().into_owned();
+        let json = fs::read_to_string(&fixture).unwrap();
+
+        let rendered = match render_graph_json(&json, kind) {
+            Ok(rendered) => rendered,
+            Err(EngineError::Gpu { message }) if kind == BackendKind::Gpu => {
+                eprintln!("skipping GPU golden images, no usable adapter: {}", message);
+                return;
+            }
+            Err(e) => {
+                failures.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+        let actual = RgbaImage::from_raw(rendered.width, rendered.height, rendered.to_rgba8())
+            .expect("rendered images convert to tightly packed RGBA8");
+
+        if name.ends_with("_tileable") {
+            if let Some(seam) = seam(&actual) {
+                failures.push(format!("{}: {}", name, seam));
+                continue;
+            }
+        }
+
+        let reference = fixture.with_extension("png");
+        if bless {
+            actual.save(&reference).unwrap();
+            continue;
+        }
+        let expected = match image::open(&reference) {
+            Ok(expected) => expected.to_rgba8(),
+            Err(e) => {
+                failures.push(format!(
+                    "{}: cannot read {} ({}); run with PIXEL_FORGE_BLESS=1 to create it",
+                    name,
+                    reference.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+
+        if let Some(mismatch) = compare(&expected, &actual) {
+            fs::create_dir_all(&out_dir).unwrap();
+            actual
+                .save(out_dir.join(format!("{}-{}-actual.png", name, label)))
+                .unwrap();
+            let mut message = format!("{}: {}", name, mismatch);
+            if expected.dimensions() == actual.dimensions() {
+                let diff_path = out_dir.join(format!("{}-{}-diff.png", name, label));
+                diff_image(&expected, &actual).save(&diff_path).unwrap();
+                message += &format!(", diff written to {}", diff_path.display());
+            }
+            failures.push(message);
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} golden image(s) failed on the {} backend:\n{}",
+        failures.len(),
+        label,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn golden_images_cpu() {
+    check_fixtures(BackendKind::Cpu, "cpu");
+}
+
+/// Runs on whatever adapter wgpu finds, including software ones such as
+/// llvmpipe, and is skipped when there is none.
+#[test]
+fn golden_images_gpu() {
+    check_fixtures(BackendKind::Gpu, "gpu");
+}
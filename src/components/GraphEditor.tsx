@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import {
 	addEdge,
 	Background,
@@ -16,13 +17,63 @@ import '@xyflow/react/dist/style.css';
 import { useCallback, useEffect, useState } from 'react';
 
 import ColorNode from './nodes/ColorNode';
+import GradientNode from './nodes/GradientNode';
+import ImageNode from './nodes/ImageNode';
 import MixNode from './nodes/MixNode';
-import OutputNode from './nodes/OutputNode';
+import NoiseNode from './nodes/NoiseNode';
+import OutputNode, { type RenderedImage } from './nodes/OutputNode';
+import PatternNode from './nodes/PatternNode';
+import ShapeNode from './nodes/ShapeNode';
+
+// Mirrors `EngineError` in src-tauri/src/error.rs.
+export type EngineError =
+	| { kind: 'validation'; message: string; diagnostics: unknown[] }
+	| {
+			kind: 'nodeEvaluation';
+			nodeId: string;
+			handle: string | null;
+			message: string;
+	  }
+	| { kind: 'gpu'; message: string }
+	| { kind: 'io'; message: string }
+	| { kind: 'cancelled'; message: string };
+
+// Mirrors `RenderEvent` in src-tauri/src/renderer.rs.
+type RenderProgress = { job: number; finishedSteps: number; totalSteps: number };
+type RenderFinished = {
+	job: number;
+	renderedNodes: string[];
+	error: EngineError | null;
+};
+type DeviceLost = { reason: string; error: EngineError | null };
+
+// Mirrors `RenderStats` in src-tauri/src/stats.rs.
+type RenderStats = {
+	backend: string;
+	timing: 'gpu' | 'cpu';
+	totalMs: number;
+	nodes: {
+		nodeId: string;
+		nodeType: string;
+		cached: boolean;
+		durationMs: number | null;
+	}[];
+	renderedNodes: string[];
+	cacheHits: number;
+	cacheMisses: number;
+	cachedBytes: number;
+	pooledBytes: number;
+};
 
 const nodeTypes = {
 	colorNode: ColorNode,
 	outputNode: OutputNode,
 	mixNode: MixNode,
+	imageNode: ImageNode,
+	noiseNode: NoiseNode,
+	shapeNode: ShapeNode,
+	gradientNode: GradientNode,
+	patternNode: PatternNode,
 };
 
 const initialNodes: Node[] = [
@@ -42,6 +93,9 @@ const initialNodes: Node[] = [
 
 const initialEdges: Edge[] = [];
 
+/** The working space the engine renders a graph in. */
+type ColorSpace = 'linear' | 'gamma';
+
 function GraphEditor() {
 	const [nodes, setNodes, onNodesChange] = useNodesState(initialNodes);
 	const [edges, setEdges, onEdgesChange] = useEdgesState(initialEdges);
@@ -51,16 +105,47 @@ function GraphEditor() {
 		[setEdges],
 	);
 
+	const [colorSpace, setColorSpace] = useState<ColorSpace>('linear');
+
 	// Capture React Flow instance to get up-to-date data
 	const [rfInstance, setRfInstance] = useState<ReactFlowInstance | null>(null);
 
-	// Initialize WebGPU on mount
+	// Initialize the render backend on mount
 	useEffect(() => {
-		invoke('init_wgpu')
-			.then(() => console.log('WebGPU Initialized'))
+		invoke<string>('init_backend')
+			.then((info) => console.log(info))
 			.catch(console.error);
 	}, []);
 
+	// Follow renders running on the engine's render thread
+	const [progress, setProgress] = useState<RenderProgress | null>(null);
+	useEffect(() => {
+		const unlisten = [
+			listen<RenderProgress>('render-progress', (event) =>
+				setProgress(event.payload),
+			),
+			listen<RenderFinished>('render-finished', () => setProgress(null)),
+			listen<DeviceLost>('device-lost', ({ payload }) => {
+				if (payload.error) {
+					console.error(
+						`GPU device lost (${payload.reason}) and could not be recreated: ${payload.error.message}`,
+					);
+				} else {
+					console.warn(
+						`GPU device lost (${payload.reason}); recreated it, cached results were lost`,
+					);
+				}
+			}),
+		];
+		return () => {
+			for (const promise of unlisten) {
+				promise.then((stop) => stop());
+			}
+		};
+	}, []);
+
+	const [stats, setStats] = useState<RenderStats | null>(null);
+
 	const handleSync = async () => {
 		if (!rfInstance) return;
 
@@ -68,14 +153,29 @@ function GraphEditor() {
 		const currentNodes = rfInstance.getNodes();
 		const currentEdges = rfInstance.getEdges();
 
-		const graph = { nodes: currentNodes, edges: currentEdges };
+		// The backend rejects unknown node data, so keep the preview image the
+		// output node stores for itself out of the payload.
+		const graph = {
+			nodes: currentNodes.map(
+				({ id, type, data: { image: _image, ...data } }) => ({
+					id,
+					type,
+					data,
+				}),
+			),
+			edges: currentEdges,
+			colorSpace,
+		};
 		try {
-			// invoke now returns Uint8Array (mapped from Vec<u8>)
-			const imageData = await invoke<Uint8Array>('sync_graph', {
+			const image = await invoke<RenderedImage>('sync_graph', {
 				graphJson: JSON.stringify(graph),
 			});
 
-			console.log('Graph synced! Received image bytes:', imageData.length);
+			console.log(
+				`Graph synced! Received ${image.width}x${image.height} image:`,
+				image.pixels.length,
+				'bytes',
+			);
 
 			// Find OutputNode and update its data
 			setNodes((nds) =>
@@ -85,15 +185,26 @@ function GraphEditor() {
 							...node,
 							data: {
 								...node.data,
-								imageData: imageData, // Pass raw bytes to node
+								image, // Pass raw pixels and size to node
 							},
 						};
 					}
 					return node;
 				}),
 			);
+
+			const stats = await invoke<RenderStats>('get_render_stats');
+			setStats(stats);
+			console.table(stats.nodes);
 		} catch (e) {
-			console.error('Failed to sync graph:', e);
+			const error = e as EngineError;
+			// A newer sync replaced this one and will update the output
+			if (error.kind === 'cancelled') return;
+			console.error(
+				`Failed to sync graph (${error.kind}):`,
+				error.message,
+				error,
+			);
 		}
 	};
 
@@ -157,8 +268,119 @@ function GraphEditor() {
 						>
 							+ Mix
 						</button>
+						<button
+							type="button"
+							onClick={() =>
+								setNodes((nds) => [
+									...nds,
+									{
+										id: crypto.randomUUID(),
+										type: 'imageNode',
+										position: {
+											x: 100 + Math.random() * 100,
+											y: 250 + Math.random() * 100,
+										},
+										data: { label: 'Image', path: '' },
+									},
+								])
+							}
+							className="bg-amber-600 hover:bg-amber-700 text-white text-xs py-1 px-2 rounded"
+						>
+							+ Image
+						</button>
+						<button
+							type="button"
+							onClick={() =>
+								setNodes((nds) => [
+									...nds,
+									{
+										id: crypto.randomUUID(),
+										type: 'noiseNode',
+										position: {
+											x: 100 + Math.random() * 100,
+											y: 250 + Math.random() * 100,
+										},
+										data: { label: 'Noise' },
+									},
+								])
+							}
+							className="bg-slate-600 hover:bg-slate-700 text-white text-xs py-1 px-2 rounded"
+						>
+							+ Noise
+						</button>
+						<button
+							type="button"
+							onClick={() =>
+								setNodes((nds) => [
+									...nds,
+									{
+										id: crypto.randomUUID(),
+										type: 'shapeNode',
+										position: {
+											x: 100 + Math.random() * 100,
+											y: 250 + Math.random() * 100,
+										},
+										data: { label: 'Shape' },
+									},
+								])
+							}
+							className="bg-orange-600 hover:bg-orange-700 text-white text-xs py-1 px-2 rounded"
+						>
+							+ Shape
+						</button>
+						<button
+							type="button"
+							onClick={() =>
+								setNodes((nds) => [
+									...nds,
+									{
+										id: crypto.randomUUID(),
+										type: 'gradientNode',
+										position: {
+											x: 100 + Math.random() * 100,
+											y: 250 + Math.random() * 100,
+										},
+										data: { label: 'Gradient' },
+									},
+								])
+							}
+							className="bg-stone-600 hover:bg-stone-700 text-white text-xs py-1 px-2 rounded"
+						>
+							+ Gradient
+						</button>
+						<button
+							type="button"
+							onClick={() =>
+								setNodes((nds) => [
+									...nds,
+									{
+										id: crypto.randomUUID(),
+										type: 'patternNode',
+										position: {
+											x: 100 + Math.random() * 100,
+											y: 250 + Math.random() * 100,
+										},
+										data: { label: 'Pattern' },
+									},
+								])
+							}
+							className="bg-teal-600 hover:bg-teal-700 text-white text-xs py-1 px-2 rounded"
+						>
+							+ Pattern
+						</button>
 					</div>
 					<hr className="border-slate-600 my-1" />
+					<label className="text-white text-xs flex flex-col gap-1">
+						<span>Blending</span>
+						<select
+							value={colorSpace}
+							onChange={(e) => setColorSpace(e.target.value as ColorSpace)}
+							className="bg-slate-700 rounded py-1 px-2"
+						>
+							<option value="linear">Linear</option>
+							<option value="gamma">Gamma (classic)</option>
+						</select>
+					</label>
 					<button
 						type="button"
 						onClick={handleSync}
@@ -166,6 +388,21 @@ function GraphEditor() {
 					>
 						Sync Graph
 					</button>
+					{progress && (
+						<div className="text-white text-xs text-center">
+							Rendering {progress.finishedSteps}/{progress.totalSteps}
+						</div>
+					)}
+					{stats && (
+						<div
+							className="text-slate-300 text-xs text-center"
+							title={`Rendered: ${stats.renderedNodes.join(', ') || 'nothing'}`}
+						>
+							{stats.totalMs.toFixed(1)} ms, {stats.cacheHits} cached /{' '}
+							{stats.cacheMisses} rendered,{' '}
+							{((stats.cachedBytes + stats.pooledBytes) / 2 ** 20).toFixed(1)} MiB
+						</div>
+					)}
 				</Panel>
 			</ReactFlow>
 		</div>
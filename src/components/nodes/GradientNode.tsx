@@ -0,0 +1,222 @@
+import { Handle, Position, useReactFlow } from '@xyflow/react';
+import { useEffect, useState } from 'react';
+
+import PointInput, { type Point } from './PointInput';
+
+type GradientKind = 'linear' | 'radial' | 'angular' | 'diamond';
+type Color = { r: number; g: number; b: number; a: number };
+
+// Mirrors `ColorStop` in src-tauri/src/graph.rs.
+type ColorStop = { position: number; color: Color };
+
+// Mirrors `GradientParams` in src-tauri/src/nodes/gradient.rs.
+type GradientSettings = {
+	gradient: GradientKind;
+	from: Point;
+	// Left to the backend until set, which ends the gradient at the top
+	// right pixel of the canvas.
+	to: Point | null;
+	stops: ColorStop[];
+	banding: 'smooth' | 'bands' | 'dither';
+	bands: number;
+};
+
+const defaultGradient: GradientSettings = {
+	gradient: 'linear',
+	from: { x: 0, y: 0 },
+	to: null,
+	stops: [
+		{ position: 0, color: { r: 0, g: 0, b: 0, a: 1 } },
+		{ position: 1, color: { r: 255, g: 255, b: 255, a: 1 } },
+	],
+	banding: 'smooth',
+	bands: 4,
+};
+
+function toHex({ r, g, b }: Color) {
+	return `#${[r, g, b].map((c) => c.toString(16).padStart(2, '0')).join('')}`;
+}
+
+function fromHex(hex: string, a: number): Color {
+	return {
+		r: Number.parseInt(hex.slice(1, 3), 16),
+		g: Number.parseInt(hex.slice(3, 5), 16),
+		b: Number.parseInt(hex.slice(5, 7), 16),
+		a,
+	};
+}
+
+function GradientNode({
+	id,
+	data,
+}: {
+	id: string;
+	data: { label: string } & Partial<GradientSettings>;
+}) {
+	const { updateNodeData } = useReactFlow();
+	const [settings, setSettings] = useState<GradientSettings>({
+		gradient: data.gradient ?? defaultGradient.gradient,
+		from: data.from ?? defaultGradient.from,
+		to: data.to ?? defaultGradient.to,
+		stops: data.stops ?? defaultGradient.stops,
+		banding: data.banding ?? defaultGradient.banding,
+		bands: data.bands ?? defaultGradient.bands,
+	});
+
+	useEffect(() => {
+		updateNodeData(id, settings);
+	}, [settings, id, updateNodeData]);
+
+	const set = <K extends keyof GradientSettings>(key: K, value: GradientSettings[K]) =>
+		setSettings((prev) => ({ ...prev, [key]: value }));
+
+	const setStop = (index: number, stop: ColorStop) =>
+		set(
+			'stops',
+			settings.stops.map((s, i) => (i === index ? stop : s)),
+		);
+
+	// A new stop goes halfway along the widest gap, in the color of the stop
+	// before it.
+	const addStop = () => {
+		const sorted = [...settings.stops].sort((a, b) => a.position - b.position);
+		let at = 0;
+		for (let i = 1; i < sorted.length; i++) {
+			if (
+				sorted[i].position - sorted[i - 1].position >
+				sorted[at + 1].position - sorted[at].position
+			) {
+				at = i - 1;
+			}
+		}
+		const position =
+			sorted.length > 1 ? (sorted[at].position + sorted[at + 1].position) / 2 : 0.5;
+		set('stops', [...settings.stops, { position, color: sorted[at].color }]);
+	};
+
+	const preview = [...settings.stops]
+		.sort((a, b) => a.position - b.position)
+		.map(({ position, color }) => `${toHex(color)} ${position * 100}%`)
+		.join(', ');
+
+	return (
+		<div className="px-4 py-2 shadow-md rounded-md bg-white border-2 border-stone-400 min-w-[190px]">
+			<div className="flex flex-col gap-1">
+				<div className="font-bold text-sm mb-1">Gradient</div>
+
+				<select
+					value={settings.gradient}
+					onChange={(e) => set('gradient', e.target.value as GradientKind)}
+					className="nodrag text-xs border border-gray-300 rounded"
+				>
+					<option value="linear">Linear</option>
+					<option value="radial">Radial</option>
+					<option value="angular">Angular</option>
+					<option value="diamond">Diamond</option>
+				</select>
+
+				<PointInput
+					label={settings.gradient === 'linear' ? 'Start' : 'Center'}
+					value={settings.from}
+					onChange={(from) => set('from', from)}
+				/>
+				<PointInput
+					label="End"
+					value={settings.to}
+					placeholder="edge"
+					onChange={(to) => set('to', to)}
+				/>
+
+				<div
+					className="h-3 rounded border border-gray-300"
+					style={{
+						background:
+							settings.stops.length > 1
+								? `linear-gradient(to right, ${preview})`
+								: toHex(settings.stops[0].color),
+					}}
+				/>
+
+				{settings.stops.map((stop, index) => (
+					<div
+						// biome-ignore lint/suspicious/noArrayIndexKey: stops have no identity of their own
+						key={index}
+						className="text-xs flex items-center gap-1"
+					>
+						<input
+							type="number"
+							min="0"
+							max="1"
+							step="0.05"
+							value={stop.position}
+							onChange={(e) =>
+								setStop(index, { ...stop, position: Number(e.target.value) })
+							}
+							className="nodrag w-14 border border-gray-300 rounded px-1"
+						/>
+						<input
+							type="color"
+							value={toHex(stop.color)}
+							onChange={(e) =>
+								setStop(index, { ...stop, color: fromHex(e.target.value, stop.color.a) })
+							}
+							className="nodrag w-10 h-6"
+						/>
+						<button
+							type="button"
+							disabled={settings.stops.length <= 1}
+							onClick={() =>
+								set(
+									'stops',
+									settings.stops.filter((_, i) => i !== index),
+								)
+							}
+							className="nodrag ml-auto px-1 text-gray-500 hover:text-red-600 disabled:opacity-30"
+						>
+							×
+						</button>
+					</div>
+				))}
+				<button
+					type="button"
+					onClick={addStop}
+					className="nodrag text-xs border border-gray-300 rounded hover:bg-gray-100"
+				>
+					+ Stop
+				</button>
+
+				<select
+					value={settings.banding}
+					onChange={(e) => set('banding', e.target.value as GradientSettings['banding'])}
+					className="nodrag text-xs border border-gray-300 rounded"
+				>
+					<option value="smooth">Smooth</option>
+					<option value="bands">Bands</option>
+					<option value="dither">Dithered</option>
+				</select>
+
+				{settings.banding === 'bands' && (
+					<label className="text-xs flex justify-between items-center gap-2">
+						<span>Bands</span>
+						<input
+							type="number"
+							min="2"
+							max="256"
+							value={settings.bands}
+							onChange={(e) => set('bands', Number(e.target.value))}
+							className="nodrag w-12 border border-gray-300 rounded px-1"
+						/>
+					</label>
+				)}
+			</div>
+
+			<Handle
+				type="source"
+				position={Position.Right}
+				className="w-16 !bg-stone-500"
+			/>
+		</div>
+	);
+}
+
+export default GradientNode;
@@ -0,0 +1,47 @@
+import { Handle, Position, useReactFlow } from '@xyflow/react';
+import { useEffect, useState } from 'react';
+
+function ImageNode({
+	id,
+	data,
+}: {
+	id: string;
+	data: {
+		label: string;
+		path?: string;
+	};
+}) {
+	const { updateNodeData } = useReactFlow();
+	const [path, setPath] = useState(data.path ?? '');
+
+	useEffect(() => {
+		updateNodeData(id, { path });
+	}, [path, id, updateNodeData]);
+
+	return (
+		<div className="px-4 py-2 shadow-md rounded-md bg-white border-2 border-stone-400">
+			<div className="flex flex-col">
+				<div className="font-bold text-sm mb-2">Image</div>
+
+				<label className="text-xs flex flex-col gap-1">
+					<span>File (PNG, GIF, BMP, JPEG, TGA)</span>
+					<input
+						type="text"
+						value={path}
+						placeholder="/path/to/sprite.png"
+						onChange={(e) => setPath(e.target.value)}
+						className="nodrag w-48 border border-gray-300 rounded px-1"
+					/>
+				</label>
+			</div>
+
+			<Handle
+				type="source"
+				position={Position.Right}
+				className="w-16 !bg-amber-500"
+			/>
+		</div>
+	);
+}
+
+export default ImageNode;
@@ -1,5 +1,9 @@
 import { Handle, Position, useReactFlow } from '@xyflow/react';
 import { useEffect, useState } from 'react';
+import SamplerSelect, {
+	defaultSampler,
+	type SamplerSettings,
+} from './SamplerSelect';
 
 function MixNode({
 	id,
@@ -9,14 +13,16 @@ function MixNode({
 	data: {
 		label: string;
 		factor?: number;
+		samplers?: Partial<Record<'a' | 'b', SamplerSettings>>;
 	};
 }) {
 	const { updateNodeData } = useReactFlow();
 	const [factor, setFactor] = useState(data.factor ?? 0.5);
+	const [samplers, setSamplers] = useState(data.samplers ?? {});
 
 	useEffect(() => {
-		updateNodeData(id, { factor });
-	}, [factor, id, updateNodeData]);
+		updateNodeData(id, { factor, samplers });
+	}, [factor, samplers, id, updateNodeData]);
 
 	return (
 		<div className="px-4 py-2 shadow-md rounded-md bg-white border-2 border-stone-400 min-w-[150px]">
@@ -28,6 +34,10 @@ function MixNode({
 					<div className="absolute left-0 top-0 flex flex-col gap-3 w-full">
 						<div className="flex items-center relative">
 							<span className="text-xs ml-2">A</span>
+							<SamplerSelect
+								value={samplers.a ?? defaultSampler}
+								onChange={(a) => setSamplers((s) => ({ ...s, a }))}
+							/>
 							<Handle
 								type="target"
 								position={Position.Left}
@@ -38,6 +48,10 @@ function MixNode({
 						</div>
 						<div className="flex items-center relative mt-4">
 							<span className="text-xs ml-2">B</span>
+							<SamplerSelect
+								value={samplers.b ?? defaultSampler}
+								onChange={(b) => setSamplers((s) => ({ ...s, b }))}
+							/>
 							<Handle
 								type="target"
 								position={Position.Left}
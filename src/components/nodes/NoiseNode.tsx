@@ -0,0 +1,133 @@
+import { Handle, Position, useReactFlow } from '@xyflow/react';
+import { useEffect, useState } from 'react';
+
+type NoiseKind = 'value' | 'perlin' | 'simplex' | 'worley';
+
+// Mirrors `NoiseParams` in src-tauri/src/nodes/noise.rs.
+type NoiseSettings = {
+	noise: NoiseKind;
+	seed: number;
+	scale: number;
+	octaves: number;
+	lacunarity: number;
+	gain: number;
+	tileable: boolean;
+};
+
+const defaultNoise: NoiseSettings = {
+	noise: 'perlin',
+	seed: 0,
+	scale: 4,
+	octaves: 1,
+	lacunarity: 2,
+	gain: 0.5,
+	tileable: false,
+};
+
+const sliders: {
+	key: 'scale' | 'octaves' | 'lacunarity' | 'gain';
+	label: string;
+	min: number;
+	max: number;
+	step: number;
+}[] = [
+	{ key: 'scale', label: 'Scale', min: 1, max: 64, step: 0.5 },
+	{ key: 'octaves', label: 'Octaves', min: 1, max: 8, step: 1 },
+	{ key: 'lacunarity', label: 'Lacunarity', min: 1, max: 4, step: 0.05 },
+	{ key: 'gain', label: 'Gain', min: 0.01, max: 1, step: 0.01 },
+];
+
+function NoiseNode({
+	id,
+	data,
+}: {
+	id: string;
+	data: { label: string } & Partial<NoiseSettings>;
+}) {
+	const { updateNodeData } = useReactFlow();
+	const [settings, setSettings] = useState<NoiseSettings>({
+		noise: data.noise ?? defaultNoise.noise,
+		seed: data.seed ?? defaultNoise.seed,
+		scale: data.scale ?? defaultNoise.scale,
+		octaves: data.octaves ?? defaultNoise.octaves,
+		lacunarity: data.lacunarity ?? defaultNoise.lacunarity,
+		gain: data.gain ?? defaultNoise.gain,
+		tileable: data.tileable ?? defaultNoise.tileable,
+	});
+
+	useEffect(() => {
+		updateNodeData(id, settings);
+	}, [settings, id, updateNodeData]);
+
+	const set = <K extends keyof NoiseSettings>(key: K, value: NoiseSettings[K]) =>
+		setSettings((prev) => ({ ...prev, [key]: value }));
+
+	return (
+		<div className="px-4 py-2 shadow-md rounded-md bg-white border-2 border-stone-400 min-w-[160px]">
+			<div className="flex flex-col gap-1">
+				<div className="font-bold text-sm mb-1">Noise</div>
+
+				<select
+					value={settings.noise}
+					onChange={(e) => set('noise', e.target.value as NoiseKind)}
+					className="nodrag text-xs border border-gray-300 rounded"
+				>
+					<option value="value">Value</option>
+					<option value="perlin">Perlin</option>
+					<option value="simplex">Simplex</option>
+					<option value="worley">Worley</option>
+				</select>
+
+				<label className="text-xs flex justify-between items-center gap-2">
+					<span>Seed</span>
+					<input
+						type="number"
+						min="0"
+						step="1"
+						value={settings.seed}
+						onChange={(e) =>
+							set('seed', Math.max(0, Math.floor(Number(e.target.value))))
+						}
+						className="nodrag w-20 border border-gray-300 rounded px-1"
+					/>
+				</label>
+
+				{sliders.map(({ key, label, min, max, step }) => (
+					<label key={key} className="text-xs flex flex-col">
+						<span className="flex justify-between">
+							<span>{label}</span>
+							<span>{settings[key]}</span>
+						</span>
+						<input
+							type="range"
+							min={min}
+							max={max}
+							step={step}
+							value={settings[key]}
+							onChange={(e) => set(key, Number(e.target.value))}
+							className="nodrag w-full"
+						/>
+					</label>
+				))}
+
+				<label className="text-xs flex items-center gap-2">
+					<input
+						type="checkbox"
+						checked={settings.tileable}
+						onChange={(e) => set('tileable', e.target.checked)}
+						className="nodrag"
+					/>
+					<span>Tileable</span>
+				</label>
+			</div>
+
+			<Handle
+				type="source"
+				position={Position.Right}
+				className="w-16 !bg-slate-500"
+			/>
+		</div>
+	);
+}
+
+export default NoiseNode;
@@ -1,27 +1,85 @@
-import { Handle, Position } from '@xyflow/react';
-import { useEffect, useRef } from 'react';
+import { Handle, Position, useReactFlow } from '@xyflow/react';
+import { useEffect, useRef, useState } from 'react';
+
+/** How the pixels of a `RenderedImage` are encoded. */
+export type OutputEncoding = 'rgba8' | 'srgb8' | 'gray8' | 'rgba16f';
+
+/** Pixels returned by `sync_graph`, row by row without padding. */
+export type RenderedImage = {
+	width: number;
+	height: number;
+	encoding: OutputEncoding;
+	pixels: number[];
+};
+
+/** Decodes a little-endian IEEE half-precision float. */
+function halfToFloat(bits: number): number {
+	const sign = bits & 0x8000 ? -1 : 1;
+	const exponent = (bits >> 10) & 0x1f;
+	const fraction = bits & 0x3ff;
+	if (exponent === 0) return sign * 2 ** -14 * (fraction / 1024);
+	if (exponent === 0x1f) return fraction ? Number.NaN : sign * Infinity;
+	return sign * 2 ** (exponent - 15) * (1 + fraction / 1024);
+}
+
+/** Converts any output encoding to the RGBA8 a canvas displays. */
+function toRgba8(image: RenderedImage): Uint8ClampedArray {
+	const { pixels } = image;
+	switch (image.encoding) {
+		case 'gray8': {
+			const rgba = new Uint8ClampedArray(pixels.length * 4);
+			pixels.forEach((value, i) => {
+				rgba.set([value, value, value, 255], i * 4);
+			});
+			return rgba;
+		}
+		case 'rgba16f': {
+			const rgba = new Uint8ClampedArray(pixels.length / 2);
+			for (let i = 0; i < rgba.length; i++) {
+				const bits = pixels[i * 2] | (pixels[i * 2 + 1] << 8);
+				rgba[i] = Math.round(halfToFloat(bits) * 255);
+			}
+			return rgba;
+		}
+		default:
+			return new Uint8ClampedArray(pixels);
+	}
+}
 
 function OutputNode({
+	id,
 	data,
 }: {
-	data: { label: string; imageData?: Uint8Array };
+	id: string;
+	data: { label: string; encoding?: OutputEncoding; image?: RenderedImage };
 }) {
+	const { updateNodeData } = useReactFlow();
+	const [encoding, setEncoding] = useState<OutputEncoding>(
+		data.encoding ?? 'srgb8',
+	);
+
+	useEffect(() => {
+		updateNodeData(id, { encoding });
+	}, [encoding, id, updateNodeData]);
+
 	const canvasRef = useRef<HTMLCanvasElement>(null);
 
 	useEffect(() => {
-		if (data.imageData && canvasRef.current) {
+		if (data.image && canvasRef.current) {
 			const canvas = canvasRef.current;
+			canvas.width = data.image.width;
+			canvas.height = data.image.height;
 			const ctx = canvas.getContext('2d');
 			if (ctx) {
 				const imageData = new ImageData(
-					new Uint8ClampedArray(data.imageData),
-					256,
-					256,
+					toRgba8(data.image),
+					data.image.width,
+					data.image.height,
 				);
 				ctx.putImageData(imageData, 0, 0);
 			}
 		}
-	}, [data.imageData]);
+	}, [data.image]);
 
 	return (
 		<div className="px-4 py-2 shadow-md rounded-md bg-slate-900 border-2 border-stone-400 text-white">
@@ -36,6 +94,20 @@ function OutputNode({
 						className="w-32 h-32 object-contain rendering-pixelated"
 					/>
 				</div>
+
+				<label className="text-xs flex gap-2 items-center mt-2">
+					<span>Encoding</span>
+					<select
+						value={encoding}
+						onChange={(e) => setEncoding(e.target.value as OutputEncoding)}
+						className="nodrag bg-slate-800 border border-gray-600 rounded"
+					>
+						<option value="srgb8">sRGB8</option>
+						<option value="rgba8">RGBA8</option>
+						<option value="gray8">Gray8</option>
+						<option value="rgba16f">RGBA16F</option>
+					</select>
+				</label>
 			</div>
 
 			<Handle
@@ -0,0 +1,166 @@
+import { Handle, Position, useReactFlow } from '@xyflow/react';
+import { useEffect, useState } from 'react';
+
+import PointInput, { type Point } from './PointInput';
+
+type PatternKind = 'checker' | 'stripes' | 'bricks' | 'hex' | 'dots';
+type Color = { r: number; g: number; b: number; a: number };
+
+// Mirrors `PatternParams` in src-tauri/src/nodes/pattern.rs.
+type PatternSettings = {
+	pattern: PatternKind;
+	width: number;
+	height: number;
+	gap: number;
+	offset: Point;
+	direction: 'vertical' | 'horizontal' | 'diagonal';
+	color: Color;
+	background: Color;
+};
+
+const defaultPattern: PatternSettings = {
+	pattern: 'checker',
+	width: 8,
+	height: 8,
+	gap: 1,
+	offset: { x: 0, y: 0 },
+	direction: 'vertical',
+	color: { r: 255, g: 255, b: 255, a: 1 },
+	background: { r: 0, g: 0, b: 0, a: 1 },
+};
+
+function toHex({ r, g, b }: Color) {
+	return `#${[r, g, b].map((c) => c.toString(16).padStart(2, '0')).join('')}`;
+}
+
+function fromHex(hex: string, a: number): Color {
+	return {
+		r: Number.parseInt(hex.slice(1, 3), 16),
+		g: Number.parseInt(hex.slice(3, 5), 16),
+		b: Number.parseInt(hex.slice(5, 7), 16),
+		a,
+	};
+}
+
+function PatternNode({
+	id,
+	data,
+}: {
+	id: string;
+	data: { label: string } & Partial<PatternSettings>;
+}) {
+	const { updateNodeData } = useReactFlow();
+	const [settings, setSettings] = useState<PatternSettings>({
+		pattern: data.pattern ?? defaultPattern.pattern,
+		width: data.width ?? defaultPattern.width,
+		height: data.height ?? defaultPattern.height,
+		gap: data.gap ?? defaultPattern.gap,
+		offset: data.offset ?? defaultPattern.offset,
+		direction: data.direction ?? defaultPattern.direction,
+		color: data.color ?? defaultPattern.color,
+		background: data.background ?? defaultPattern.background,
+	});
+
+	useEffect(() => {
+		updateNodeData(id, settings);
+	}, [settings, id, updateNodeData]);
+
+	const set = <K extends keyof PatternSettings>(key: K, value: PatternSettings[K]) =>
+		setSettings((prev) => ({ ...prev, [key]: value }));
+
+	const stripes = settings.pattern === 'stripes';
+
+	return (
+		<div className="px-4 py-2 shadow-md rounded-md bg-white border-2 border-stone-400 min-w-[170px]">
+			<div className="flex flex-col gap-1">
+				<div className="font-bold text-sm mb-1">Pattern</div>
+
+				<select
+					value={settings.pattern}
+					onChange={(e) => set('pattern', e.target.value as PatternKind)}
+					className="nodrag text-xs border border-gray-300 rounded"
+				>
+					<option value="checker">Checker</option>
+					<option value="stripes">Stripes</option>
+					<option value="bricks">Bricks</option>
+					<option value="hex">Hexagons</option>
+					<option value="dots">Dots</option>
+				</select>
+
+				{stripes && (
+					<select
+						value={settings.direction}
+						onChange={(e) =>
+							set('direction', e.target.value as PatternSettings['direction'])
+						}
+						className="nodrag text-xs border border-gray-300 rounded"
+					>
+						<option value="vertical">Vertical</option>
+						<option value="horizontal">Horizontal</option>
+						<option value="diagonal">Diagonal</option>
+					</select>
+				)}
+
+				{(['width', 'height', 'gap'] as const)
+					.filter(
+						(key) =>
+							!stripes ||
+							key === 'gap' ||
+							(key === 'height') === (settings.direction === 'horizontal'),
+					)
+					.map((key) => (
+						<label
+							key={key}
+							className="text-xs flex justify-between items-center gap-2"
+						>
+							<span className="capitalize">{key}</span>
+							<input
+								type="number"
+								min={key === 'gap' ? 0 : 1}
+								max="1024"
+								step="1"
+								value={settings[key]}
+								onChange={(e) => set(key, Math.round(Number(e.target.value)))}
+								className="nodrag w-12 border border-gray-300 rounded px-1"
+							/>
+						</label>
+					))}
+
+				<PointInput
+					label="Offset"
+					value={settings.offset}
+					onChange={(offset) => set('offset', offset)}
+				/>
+
+				{(['color', 'background'] as const).map((key) => (
+					<label key={key} className="text-xs flex justify-between items-center gap-2">
+						<span className="capitalize">{key}</span>
+						<input
+							type="color"
+							value={toHex(settings[key])}
+							onChange={(e) => set(key, fromHex(e.target.value, settings[key].a))}
+							className="nodrag w-12 h-6"
+						/>
+					</label>
+				))}
+			</div>
+
+			<Handle
+				type="source"
+				position={Position.Right}
+				id="out"
+				className="w-16 !bg-stone-500"
+				style={{ top: '40%' }}
+			/>
+			<Handle
+				type="source"
+				position={Position.Right}
+				id="mask"
+				className="w-16 !bg-gray-500"
+				style={{ top: '60%' }}
+			/>
+		</div>
+	);
+}
+
+export default PatternNode;
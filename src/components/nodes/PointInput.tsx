@@ -0,0 +1,44 @@
+// Mirrors `Point` in src-tauri/src/graph.rs.
+export type Point = { x: number; y: number };
+
+/**
+ * A labelled pair of whole-pixel coordinate inputs. A point that is left to
+ * the backend is `null`, shown as empty inputs marked `placeholder`.
+ */
+function PointInput({
+	label,
+	value,
+	placeholder,
+	onChange,
+}: {
+	label: string;
+	value: Point | null;
+	placeholder?: string;
+	onChange: (value: Point) => void;
+}) {
+	return (
+		<label className="text-xs flex justify-between items-center gap-2">
+			<span>{label}</span>
+			<span className="flex gap-1">
+				{(['x', 'y'] as const).map((axis) => (
+					<input
+						key={axis}
+						type="number"
+						step="1"
+						value={value?.[axis] ?? ''}
+						placeholder={placeholder}
+						onChange={(e) =>
+							onChange({
+								...(value ?? { x: 0, y: 0 }),
+								[axis]: Math.round(Number(e.target.value)),
+							})
+						}
+						className="nodrag w-12 border border-gray-300 rounded px-1"
+					/>
+				))}
+			</span>
+		</label>
+	);
+}
+
+export default PointInput;
@@ -0,0 +1,56 @@
+// Mirrors `SamplerSettings` in src-tauri/src/samplers.rs.
+export type SamplerSettings = {
+	filter: 'nearest' | 'linear';
+	address: 'clamp' | 'repeat' | 'mirror' | 'border';
+};
+
+export const defaultSampler: SamplerSettings = {
+	filter: 'nearest',
+	address: 'clamp',
+};
+
+/** Filter and address mode pickers for how a node samples one input. */
+function SamplerSelect({
+	value,
+	onChange,
+}: {
+	value: SamplerSettings;
+	onChange: (value: SamplerSettings) => void;
+}) {
+	return (
+		<span className="flex gap-1 ml-auto">
+			<select
+				value={value.filter}
+				onChange={(e) =>
+					onChange({
+						...value,
+						filter: e.target.value as SamplerSettings['filter'],
+					})
+				}
+				className="nodrag text-xs border rounded"
+				title="Filter"
+			>
+				<option value="nearest">Nearest</option>
+				<option value="linear">Linear</option>
+			</select>
+			<select
+				value={value.address}
+				onChange={(e) =>
+					onChange({
+						...value,
+						address: e.target.value as SamplerSettings['address'],
+					})
+				}
+				className="nodrag text-xs border rounded"
+				title="Outside the edges"
+			>
+				<option value="clamp">Clamp</option>
+				<option value="repeat">Repeat</option>
+				<option value="mirror">Mirror</option>
+				<option value="border">Transparent</option>
+			</select>
+		</span>
+	);
+}
+
+export default SamplerSelect;
@@ -0,0 +1,178 @@
+import { Handle, Position, useReactFlow } from '@xyflow/react';
+import { useEffect, useState } from 'react';
+
+import PointInput, { type Point } from './PointInput';
+
+type ShapeKind = 'rect' | 'ellipse' | 'polygon' | 'line';
+
+// Mirrors `ShapeParams` in src-tauri/src/nodes/shape.rs.
+type ShapeSettings = {
+	shape: ShapeKind;
+	from: Point;
+	to: Point;
+	mode: 'fill' | 'stroke';
+	thickness: number;
+	sides: number;
+	rotation: number;
+	color: { r: number; g: number; b: number; a: number };
+};
+
+const defaultShape: ShapeSettings = {
+	shape: 'rect',
+	from: { x: 0, y: 0 },
+	to: { x: 15, y: 15 },
+	mode: 'fill',
+	thickness: 1,
+	sides: 6,
+	rotation: 0,
+	color: { r: 255, g: 255, b: 255, a: 1 },
+};
+
+function toHex({ r, g, b }: ShapeSettings['color']) {
+	return `#${[r, g, b].map((c) => c.toString(16).padStart(2, '0')).join('')}`;
+}
+
+function ShapeNode({
+	id,
+	data,
+}: {
+	id: string;
+	data: { label: string } & Partial<ShapeSettings>;
+}) {
+	const { updateNodeData } = useReactFlow();
+	const [settings, setSettings] = useState<ShapeSettings>({
+		shape: data.shape ?? defaultShape.shape,
+		from: data.from ?? defaultShape.from,
+		to: data.to ?? defaultShape.to,
+		mode: data.mode ?? defaultShape.mode,
+		thickness: data.thickness ?? defaultShape.thickness,
+		sides: data.sides ?? defaultShape.sides,
+		rotation: data.rotation ?? defaultShape.rotation,
+		color: data.color ?? defaultShape.color,
+	});
+
+	useEffect(() => {
+		updateNodeData(id, settings);
+	}, [settings, id, updateNodeData]);
+
+	const set = <K extends keyof ShapeSettings>(key: K, value: ShapeSettings[K]) =>
+		setSettings((prev) => ({ ...prev, [key]: value }));
+
+	return (
+		<div className="px-4 py-2 shadow-md rounded-md bg-white border-2 border-stone-400 min-w-[170px]">
+			<div className="flex flex-col gap-1">
+				<div className="font-bold text-sm mb-1">Shape</div>
+
+				<select
+					value={settings.shape}
+					onChange={(e) => set('shape', e.target.value as ShapeKind)}
+					className="nodrag text-xs border border-gray-300 rounded"
+				>
+					<option value="rect">Rectangle</option>
+					<option value="ellipse">Ellipse</option>
+					<option value="polygon">Polygon</option>
+					<option value="line">Line</option>
+				</select>
+
+				<PointInput
+					label={settings.shape === 'line' ? 'Start' : 'Corner'}
+					value={settings.from}
+					onChange={(from) => set('from', from)}
+				/>
+				<PointInput
+					label={settings.shape === 'line' ? 'End' : 'Corner'}
+					value={settings.to}
+					onChange={(to) => set('to', to)}
+				/>
+
+				{settings.shape !== 'line' && (
+					<select
+						value={settings.mode}
+						onChange={(e) => set('mode', e.target.value as ShapeSettings['mode'])}
+						className="nodrag text-xs border border-gray-300 rounded"
+					>
+						<option value="fill">Fill</option>
+						<option value="stroke">Stroke</option>
+					</select>
+				)}
+
+				<label className="text-xs flex justify-between items-center gap-2">
+					<span>Thickness</span>
+					<input
+						type="number"
+						min="1"
+						max="64"
+						value={settings.thickness}
+						onChange={(e) => set('thickness', Number(e.target.value))}
+						className="nodrag w-12 border border-gray-300 rounded px-1"
+					/>
+				</label>
+
+				{settings.shape === 'polygon' && (
+					<>
+						<label className="text-xs flex justify-between items-center gap-2">
+							<span>Sides</span>
+							<input
+								type="number"
+								min="3"
+								max="64"
+								value={settings.sides}
+								onChange={(e) => set('sides', Number(e.target.value))}
+								className="nodrag w-12 border border-gray-300 rounded px-1"
+							/>
+						</label>
+						<label className="text-xs flex flex-col">
+							<span className="flex justify-between">
+								<span>Rotation</span>
+								<span>{settings.rotation}°</span>
+							</span>
+							<input
+								type="range"
+								min="0"
+								max="360"
+								value={settings.rotation}
+								onChange={(e) => set('rotation', Number(e.target.value))}
+								className="nodrag w-full"
+							/>
+						</label>
+					</>
+				)}
+
+				<label className="text-xs flex justify-between items-center gap-2">
+					<span>Color</span>
+					<input
+						type="color"
+						value={toHex(settings.color)}
+						onChange={(e) => {
+							const hex = e.target.value;
+							set('color', {
+								r: Number.parseInt(hex.slice(1, 3), 16),
+								g: Number.parseInt(hex.slice(3, 5), 16),
+								b: Number.parseInt(hex.slice(5, 7), 16),
+								a: settings.color.a,
+							});
+						}}
+						className="nodrag w-12 h-6"
+					/>
+				</label>
+			</div>
+
+			<Handle
+				type="source"
+				position={Position.Right}
+				id="out"
+				className="w-16 !bg-orange-500"
+				style={{ top: '40%' }}
+			/>
+			<Handle
+				type="source"
+				position={Position.Right}
+				id="mask"
+				className="w-16 !bg-gray-500"
+				style={{ top: '60%' }}
+			/>
+		</div>
+	);
+}
+
+export default ShapeNode;